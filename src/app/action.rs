@@ -0,0 +1,501 @@
+use std::{
+    collections::HashMap,
+    env,
+    io::{self, Read, Write},
+    os::unix::process::CommandExt,
+    path::PathBuf,
+    process::{Child, Command, ExitStatus, Output, Stdio},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    config::{Action, CommandAction, Config, Confirm, Stream},
+    style::Styled,
+    theme::Theme,
+};
+
+use super::{confirm::ConfirmApp, message::MessageApp, Instruction};
+
+/// Values substituted into action templates, such as `{input}` or `{name}`.
+/// A `:url` suffix, as in `{input:url}`, percent-encodes the value.
+#[derive(Default, Clone)]
+pub struct Vars {
+    values: HashMap<String, String>,
+    selected: Vec<String>,
+    /// In the order they were added.
+    secrets: Vec<(String, String)>,
+}
+
+impl Vars {
+    pub fn with(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.values.insert(name.to_string(), value.into());
+        self
+    }
+
+    /// Sets the values of `{selected...}`.
+    pub fn with_selected(mut self, selected: Vec<String>) -> Self {
+        self.selected = selected;
+        self
+    }
+
+    /// Sets a value that is only expanded in stdin and print templates, so it
+    /// never shows up in arguments. Commands without a stdin template get it on
+    /// stdin as is.
+    pub fn with_secret(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.secrets.push((name.to_string(), value.into()));
+        self
+    }
+
+    pub fn expand(&self, template: &str) -> String {
+        self.expand_with(template, false, str::to_string)
+    }
+
+    /// Expands a template run with `sh -c`, quoting each value so it is
+    /// never run as part of the script.
+    pub fn expand_shell(&self, template: &str) -> String {
+        self.expand_with(template, false, |value| {
+            format!("'{}'", value.replace('\'', "'\\''"))
+        })
+    }
+
+    /// The text to write to a command's stdin, from its `template` if given,
+    /// or else the first secret.
+    pub fn stdin(&self, template: Option<&str>) -> Option<String> {
+        let Some(template) = template else {
+            return self.secrets.first().map(|(_, value)| value.clone());
+        };
+        Some(self.reveal(template))
+    }
+
+    /// Expands a template, including secrets.
+    pub fn reveal(&self, template: &str) -> String {
+        self.expand_with(template, true, str::to_string)
+    }
+
+    /// Replaces each placeholder in one pass, so placeholders inside values
+    /// are left as they are.
+    fn expand_with(&self, template: &str, reveal: bool, quote: impl Fn(&str) -> String) -> String {
+        let mut text = String::new();
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            text.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let expanded = rest.find('}').and_then(|end| {
+                let value = match rest[1..end].strip_suffix(":url") {
+                    Some(name) => self.lookup(name, reveal, |value| quote(&url_encode(value)))?,
+                    None => self.lookup(&rest[1..end], reveal, &quote)?,
+                };
+                Some((value, end + 1))
+            });
+            // Unknown placeholders are kept as they are.
+            let (value, len) = expanded.unwrap_or_else(|| (String::from("{"), 1));
+            text.push_str(&value);
+            rest = &rest[len..];
+        }
+        text.push_str(rest);
+        text
+    }
+
+    /// The quoted value of the placeholder `name`. Secrets are empty unless
+    /// `reveal` is set.
+    fn lookup(&self, name: &str, reveal: bool, quote: impl Fn(&str) -> String) -> Option<String> {
+        if name == "selected..." {
+            let selected: Vec<_> = self.selected.iter().map(|value| quote(value)).collect();
+            return Some(selected.join(" "));
+        }
+        if let Some(value) = self.values.get(name) {
+            return Some(quote(value));
+        }
+        let (_, secret) = self.secrets.iter().find(|(secret, _)| secret == name)?;
+        Some(quote(if reveal { secret } else { "" }))
+    }
+
+    /// Expands each argument, with a lone `{selected...}` becoming one
+    /// argument per selected value.
+    pub fn expand_args(&self, args: &[String]) -> Vec<String> {
+        args.iter()
+            .flat_map(|arg| match arg.as_str() {
+                "{selected...}" => self.selected.clone(),
+                arg => vec![self.expand(arg)],
+            })
+            .collect()
+    }
+}
+
+/// Percent-encodes everything but unreserved characters, so the text can be
+/// put anywhere in a URL.
+fn url_encode(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            byte => format!("%{byte:02X}"),
+        })
+        .collect()
+}
+
+pub fn error(err: impl ToString, theme: &Theme) -> Instruction {
+    Instruction::SetApp(Box::new(MessageApp(Styled {
+        text: err.to_string(),
+        style: theme.error.clone(),
+    })))
+}
+
+impl From<Stream> for Stdio {
+    fn from(value: Stream) -> Self {
+        match value {
+            Stream::Inherit => Stdio::inherit(),
+            Stream::Capture => Stdio::piped(),
+            Stream::Discard => Stdio::null(),
+        }
+    }
+}
+
+/// A command ready to run, with the text to write to its stdin.
+pub struct Process {
+    pub command: Command,
+    pub stdin: Option<String>,
+    /// How long to wait for the command before killing it.
+    pub timeout: Option<Duration>,
+}
+
+impl Process {
+    pub fn new(command: Command) -> Self {
+        Self {
+            command,
+            stdin: None,
+            timeout: None,
+        }
+    }
+
+    pub fn spawn(&mut self) -> io::Result<Child> {
+        let Some(stdin) = self.stdin.clone() else {
+            return self.command.spawn();
+        };
+        let mut child = self.command.stdin(Stdio::piped()).spawn()?;
+        let mut pipe = child.stdin.take().expect("stdin is piped");
+        // Written on its own thread, since the command may fill its output
+        // pipes before reading all of it.
+        thread::spawn(move || {
+            let _ = pipe.write_all(stdin.as_bytes());
+        });
+        Ok(child)
+    }
+
+    /// Runs the command to completion. Only streams set to `Stdio::piped`
+    /// are collected.
+    pub fn output(mut self) -> io::Result<Output> {
+        // In its own process group, so a timeout also kills what the command
+        // started, which would otherwise keep the output pipes open.
+        self.command.process_group(0);
+        // Reading the terminal from a background process group would stop
+        // the command, and the menu with it.
+        if self.stdin.is_none() {
+            self.command.stdin(Stdio::null());
+        }
+        let mut child = self.spawn()?;
+        let stdout = child.stdout.take().map(read_all);
+        let stderr = child.stderr.take().map(read_all);
+        let status = wait(&mut child, self.timeout)?;
+
+        let join = |reader: Option<JoinHandle<Vec<u8>>>| {
+            reader.map_or_else(Vec::new, |reader| reader.join().unwrap_or_default())
+        };
+        Ok(Output {
+            status,
+            stdout: join(stdout),
+            stderr: join(stderr),
+        })
+    }
+}
+
+/// Reads `pipe` to the end on its own thread.
+pub fn read_all(mut pipe: impl Read + Send + 'static) -> JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buffer = Vec::new();
+        let _ = pipe.read_to_end(&mut buffer);
+        buffer
+    })
+}
+
+/// Kills the process group led by `pid`, so commands run by a shell die
+/// with it. The process must have been spawned with `process_group(0)`.
+pub fn kill_group(pid: u32) -> io::Result<()> {
+    // SAFETY: killpg only sends a signal.
+    if unsafe { libc::killpg(pid as libc::pid_t, libc::SIGKILL) } == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+/// Waits for `child`, killing it and its process group, if it leads one,
+/// with a `TimedOut` error once `timeout` passes.
+pub fn wait(child: &mut Child, timeout: Option<Duration>) -> io::Result<ExitStatus> {
+    let Some(timeout) = timeout else {
+        return child.wait();
+    };
+    let start = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(status);
+        }
+        if start.elapsed() >= timeout {
+            if kill_group(child.id()).is_err() {
+                child.kill()?;
+            }
+            child.wait()?;
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("timed out after {}s", timeout.as_secs_f64()),
+            ));
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+/// Builds the process for a command action, using `default` for streams it
+/// leaves unset.
+pub fn command(action: &CommandAction, vars: &Vars, default: Stream) -> Process {
+    let stdio = |stream: Option<Stream>| match stream.unwrap_or(default) {
+        // Held output is already shown in the terminal.
+        Stream::Capture if action.hold_output => Stdio::inherit(),
+        stream => Stdio::from(stream),
+    };
+
+    let mut command = Command::new(vars.expand(&action.name));
+    command
+        .args(vars.expand_args(&action.args))
+        .stdout(stdio(action.stdout))
+        .stderr(stdio(action.stderr));
+
+    if let Some(cwd) = &action.cwd {
+        command.current_dir(expand_home(&vars.expand(cwd)));
+    }
+    if action.clear_env {
+        command.env_clear();
+    }
+    for (name, value) in &action.env {
+        command.env(name, vars.expand(value));
+    }
+
+    Process {
+        command,
+        stdin: vars.stdin(action.stdin.as_deref()),
+        timeout: action.timeout.map(Duration::from_secs_f64),
+    }
+}
+
+/// Replaces a leading `~` with the home directory.
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix('~'), env::var_os("HOME")) {
+        (Some(rest), Some(home)) => {
+            PathBuf::from(home).join(rest.strip_prefix('/').unwrap_or(rest))
+        }
+        _ => PathBuf::from(path),
+    }
+}
+
+/// The collected stdout, then stderr, of a command.
+pub fn captured(output: &Output) -> String {
+    let mut text = String::from_utf8_lossy(&output.stdout).to_string();
+    text.push_str(&String::from_utf8_lossy(&output.stderr));
+    text
+}
+
+/// Runs `action`, first asking `confirm`'s question if it is set.
+pub fn run_confirmed(
+    action: &Action,
+    confirm: Option<&Confirm>,
+    fallback: String,
+    vars: &Vars,
+    theme: &Theme,
+) -> Instruction {
+    match confirm.and_then(|confirm| confirm.question(fallback)) {
+        Some((question, default)) => Instruction::Push(Box::new(ConfirmApp {
+            question,
+            yes: default,
+            action: action.clone(),
+            vars: vars.clone(),
+            theme: theme.clone(),
+        })),
+        None => run(action, vars, theme),
+    }
+}
+
+pub fn run(action: &Action, vars: &Vars, theme: &Theme) -> Instruction {
+    execute(action, vars, theme).unwrap_or_else(|instruction| instruction)
+}
+
+/// Quits, or prints `lines` if there are any.
+fn quit_or_print(lines: Vec<String>) -> Instruction {
+    if lines.is_empty() {
+        Instruction::Quit
+    } else {
+        Instruction::Print(lines)
+    }
+}
+
+/// Runs an action, returning what to do next as an error if it failed.
+fn execute(action: &Action, vars: &Vars, theme: &Theme) -> Result<Instruction, Instruction> {
+    Ok(match action {
+        Action::Exec(name) => {
+            let output = Command::new("hyprctl")
+                .args(["dispatch", "exec"])
+                .arg(vars.expand(name))
+                .output();
+            if let Err(err) = output {
+                return Err(error(err, theme));
+            }
+            Instruction::Quit
+        }
+        Action::Command(action) => {
+            if action.hold_output {
+                if let Some((w, h)) = action.output_size {
+                    Command::new("hyprctl")
+                        .args([
+                            "--batch",
+                            &format!("dispatch resizeactive exact {w} {h}; dispatch centerwindow"),
+                        ])
+                        .output()
+                        .expect("failed to resize window");
+                    thread::sleep(Duration::from_millis(100));
+                }
+
+                Instruction::HoldOutput(Box::new(command(action, vars, Stream::Inherit)))
+            } else {
+                let output = command(action, vars, Stream::Discard)
+                    .output()
+                    .map_err(|err| error(err, theme))?;
+                let code = output
+                    .status
+                    .code()
+                    .map_or_else(|| String::from("signal"), |code| code.to_string());
+                let vars = vars.clone().with("code", code);
+
+                let follow_up = if output.status.success() {
+                    &action.on_success
+                } else {
+                    &action.on_failure
+                };
+                let instruction = match follow_up {
+                    Some(follow_up) => run(follow_up, &vars, theme),
+                    None => quit_or_print(captured(&output).lines().map(String::from).collect()),
+                };
+                if !output.status.success() {
+                    return Err(instruction);
+                }
+                instruction
+            }
+        }
+        Action::OpenMenu(name) => Instruction::OpenMenu(vars.expand(name)),
+        Action::OpenMenuFrom(action) => {
+            let mut process = command(action, vars, Stream::Capture);
+            process.command.stdout(Stdio::piped());
+            let output = process.output().map_err(|err| error(err, theme))?;
+            if !output.status.success() {
+                let mut message = format!("{} failed ({})", action.name, output.status);
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                if !stderr.trim().is_empty() {
+                    message = format!("{message}: {}", stderr.trim());
+                }
+                return Err(error(message, theme));
+            }
+
+            let config = Config::parse(&String::from_utf8_lossy(&output.stdout))
+                .map_err(|err| error(err, theme))?;
+            Instruction::OpenConfig(Box::new(config))
+        }
+        // Printing is how secret prompts hand their input to scripts.
+        Action::Print(template) => Instruction::Print(vec![vars.reveal(template)]),
+        Action::Message(template) => {
+            Instruction::SetApp(Box::new(MessageApp(Styled::from(vars.expand(template)))))
+        }
+        Action::Sequence(sequence) => {
+            let mut printed = Vec::new();
+            let mut failed = false;
+            // What the first failing action wanted to show, such as its
+            // error, shown once the sequence ends.
+            let mut failure = None;
+            for action in &sequence.actions {
+                let (instruction, ok) = match execute(action, vars, theme) {
+                    Ok(instruction) => (instruction, true),
+                    Err(instruction) => (instruction, false),
+                };
+                match instruction {
+                    Instruction::Print(lines) => printed.extend(lines),
+                    Instruction::None | Instruction::Quit => (),
+                    instruction if ok => return Ok(instruction),
+                    instruction => {
+                        failure.get_or_insert(instruction);
+                    }
+                }
+                failed |= !ok;
+                if failed && sequence.stop_on_error {
+                    break;
+                }
+            }
+            if failed {
+                return Err(failure.unwrap_or_else(|| quit_or_print(printed)));
+            }
+            quit_or_print(printed)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_are_not_expanded_again() {
+        let vars = Vars::default()
+            .with("field.host", "{field.user}")
+            .with("field.user", "root");
+        assert_eq!(
+            vars.expand("{field.user}@{field.host}"),
+            "root@{field.user}"
+        );
+    }
+
+    #[test]
+    fn revealed_secrets_are_kept_as_typed() {
+        let vars = Vars::default()
+            .with("input", "x")
+            .with_secret("password", "p{input}");
+        assert_eq!(vars.reveal("{password}"), "p{input}");
+        assert_eq!(vars.expand("[{password}]"), "[]");
+    }
+
+    #[test]
+    fn unknown_placeholders_are_kept() {
+        let vars = Vars::default().with("name", "a");
+        assert_eq!(vars.expand("{{name}} {other} {"), "{a} {other} {");
+    }
+
+    #[test]
+    fn url_values_are_percent_encoded() {
+        let vars = Vars::default().with("input", "c# tips & 100%+é");
+        assert_eq!(
+            vars.expand("?q={input:url}"),
+            "?q=c%23%20tips%20%26%20100%25%2B%C3%A9"
+        );
+        assert_eq!(
+            vars.expand_shell("{input:url}"),
+            "'c%23%20tips%20%26%20100%25%2B%C3%A9'"
+        );
+    }
+
+    #[test]
+    fn shell_values_are_quoted() {
+        let vars = Vars::default()
+            .with("name", "it's $(x)")
+            .with_selected(vec![String::from("a b"), String::from("c")]);
+        assert_eq!(
+            vars.expand_shell("echo {name} {selected...}"),
+            r#"echo 'it'\''s $(x)' 'a b' 'c'"#
+        );
+    }
+}
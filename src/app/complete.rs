@@ -0,0 +1,118 @@
+use std::{
+    collections::HashSet,
+    env, fs,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use serde::Deserialize;
+
+use super::action::Vars;
+
+/// Where prompt completions come from: the builtin `"files"` or `"path"`
+/// completers, or a command run with `sh -c` that prints one candidate per
+/// line. The command gets the input as `$1`, which `{input}` expands to, so
+/// it is never run as part of the script.
+#[derive(Deserialize, Clone)]
+#[serde(from = "String")]
+pub enum Completer {
+    Files,
+    Path,
+    /// A template using `{input}` and `{cursor}`.
+    Command(String),
+}
+
+impl From<String> for Completer {
+    fn from(value: String) -> Self {
+        match value.as_str() {
+            "files" => Completer::Files,
+            "path" => Completer::Path,
+            _ => Completer::Command(value),
+        }
+    }
+}
+
+impl Completer {
+    /// Candidates to replace `word`, the text between the last space before
+    /// the cursor and the cursor.
+    pub fn complete(&self, input: &str, cursor: usize, word: &str) -> Vec<String> {
+        let mut candidates = match self {
+            Completer::Files => files(word),
+            Completer::Path => executables(word),
+            Completer::Command(template) => {
+                let vars = Vars::default()
+                    .with("input", "\"$1\"")
+                    .with("cursor", cursor.to_string());
+                let output = Command::new("sh")
+                    .args(["-c", &vars.expand(template), "sh", input])
+                    .stdin(Stdio::null())
+                    .stderr(Stdio::null())
+                    .output();
+                match output {
+                    Ok(output) => String::from_utf8_lossy(&output.stdout)
+                        .lines()
+                        .filter(|line| !line.is_empty())
+                        .map(String::from)
+                        .collect(),
+                    Err(_) => Vec::new(),
+                }
+            }
+        };
+        let mut seen = HashSet::new();
+        candidates.retain(|candidate| seen.insert(candidate.clone()));
+        candidates
+    }
+}
+
+/// Paths starting with `word`, with a trailing `/` on directories. Hidden
+/// files are only listed once `word` names them with a leading dot.
+fn files(word: &str) -> Vec<String> {
+    let (dir, prefix) = match word.rfind('/') {
+        Some(i) => word.split_at(i + 1),
+        None => ("", word),
+    };
+    let path = match dir.strip_prefix("~/") {
+        Some(rest) => env::var_os("HOME")
+            .map(|home| Path::new(&home).join(rest))
+            .unwrap_or_else(|| PathBuf::from(dir)),
+        None if dir.is_empty() => PathBuf::from("."),
+        None => PathBuf::from(dir),
+    };
+
+    let Ok(read) = fs::read_dir(path) else {
+        return Vec::new();
+    };
+    let mut candidates: Vec<_> = read
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            if !name.starts_with(prefix) || (name.starts_with('.') && !prefix.starts_with('.')) {
+                return None;
+            }
+            let slash = if entry.path().is_dir() { "/" } else { "" };
+            Some(format!("{dir}{name}{slash}"))
+        })
+        .collect();
+    candidates.sort();
+    candidates
+}
+
+/// Executables in `$PATH` starting with `word`.
+fn executables(word: &str) -> Vec<String> {
+    let Some(path) = env::var_os("PATH") else {
+        return Vec::new();
+    };
+    let mut candidates: Vec<_> = env::split_paths(&path)
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flat_map(|read| read.flatten())
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let executable = fs::metadata(entry.path())
+                .is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0);
+            (name.starts_with(word) && executable).then_some(name)
+        })
+        .collect();
+    candidates.sort();
+    candidates
+}
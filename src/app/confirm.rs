@@ -0,0 +1,93 @@
+use std::io::{self, Stdout, Write};
+
+use termion::{
+    event::{Key, MouseButton, MouseEvent},
+    raw::RawTerminal,
+};
+
+use crate::{config::Action, keys, output::Output, style::Styled, theme::Theme};
+
+use super::{
+    action::{self, Vars},
+    App, Instruction,
+};
+
+/// Asks whether to run an action, opened over the menu it came from.
+pub struct ConfirmApp {
+    pub(super) question: String,
+    /// Whether "yes" is selected.
+    pub(super) yes: bool,
+    pub(super) action: Action,
+    pub(super) vars: Vars,
+    pub(super) theme: Theme,
+}
+
+impl ConfirmApp {
+    fn answer(&self, yes: bool) -> Instruction {
+        if yes {
+            action::run(&self.action, &self.vars, &self.theme)
+        } else {
+            Instruction::Back
+        }
+    }
+}
+
+impl App for ConfirmApp {
+    fn draw(&self, terminal: &mut RawTerminal<Stdout>) -> io::Result<()> {
+        terminal.clear()?;
+        terminal.print(" ")?;
+        terminal.print(&self.theme.prompt)?;
+        terminal.print(&self.question)?;
+        terminal.print(termion::style::Reset)?;
+
+        terminal.move_cursor(2, 3)?;
+        for (label, selected) in [("yes", self.yes), ("no", !self.yes)] {
+            let style = if selected {
+                &self.theme.highlight
+            } else {
+                &self.theme.placeholder
+            };
+            terminal.print(Styled {
+                text: format!("[ {label} ]"),
+                style: style.clone(),
+            })?;
+            terminal.print("  ")?;
+        }
+
+        terminal.flush()
+    }
+
+    fn handle_input(&mut self, key: Key) -> Instruction {
+        match key {
+            Key::Char('y') => self.answer(true),
+            Key::Char('n') => self.answer(false),
+            Key::Left | Key::Right => {
+                self.yes = !self.yes;
+                Instruction::None
+            }
+            _ => Instruction::None,
+        }
+    }
+
+    fn handle_command(&mut self, command: keys::Command) -> Instruction {
+        match command {
+            keys::Command::Accept | keys::Command::AcceptAlt => self.answer(self.yes),
+            keys::Command::SelectNext | keys::Command::SelectPrev => {
+                self.yes = !self.yes;
+                Instruction::None
+            }
+            _ => Instruction::None,
+        }
+    }
+
+    fn handle_mouse(&mut self, event: MouseEvent) -> Instruction {
+        match event {
+            // "[ yes ]" then "[ no ]", starting at column 2.
+            MouseEvent::Press(MouseButton::Left, 2..=8, 3) => self.answer(true),
+            MouseEvent::Press(MouseButton::Left, 11..=16, 3) => self.answer(false),
+            _ => Instruction::None,
+        }
+    }
+
+    fn handle_resize(&mut self, _: usize, _: usize) {}
+}
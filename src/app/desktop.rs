@@ -0,0 +1,238 @@
+use std::{
+    collections::{HashMap, HashSet},
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::config::{Action, Entry};
+
+/// Shown for applications, since their icons are images.
+const ICON: &str = "\u{f2d0}";
+
+/// The `applications` dirs of the XDG data dirs, most important first.
+fn dirs() -> Vec<PathBuf> {
+    let home = env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")));
+    let data_dirs = env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| String::from("/usr/local/share:/usr/share"));
+
+    home.into_iter()
+        .chain(
+            data_dirs
+                .split(':')
+                .filter(|dir| !dir.is_empty())
+                .map(PathBuf::from),
+        )
+        .map(|dir| dir.join("applications"))
+        .collect()
+}
+
+/// Adds the desktop files under `dir` to `found`, with their desktop file
+/// IDs.
+fn find(dir: &Path, prefix: &str, found: &mut Vec<(String, PathBuf)>) {
+    let Ok(read) = fs::read_dir(dir) else {
+        return;
+    };
+    for file in read.flatten() {
+        let path = file.path();
+        let name = file.file_name().to_string_lossy().to_string();
+        if path.is_dir() {
+            find(&path, &format!("{prefix}{name}-"), found);
+        } else if name.ends_with(".desktop") {
+            found.push((format!("{prefix}{name}"), path));
+        }
+    }
+}
+
+/// Entries for the applications in the XDG data dirs, sorted by name.
+pub fn entries(terminal: Option<&str>) -> Vec<Entry> {
+    let mut seen = HashSet::new();
+    let mut entries = Vec::new();
+    for dir in dirs() {
+        let mut found = Vec::new();
+        find(&dir, "", &mut found);
+        for (id, path) in found {
+            // A file overrides those with the same ID in later dirs, even if
+            // it hides the application.
+            if !seen.insert(id) {
+                continue;
+            }
+            if let Some(entry) = fs::read_to_string(&path)
+                .ok()
+                .and_then(|text| parse(&text, terminal))
+            {
+                entries.push(entry);
+            }
+        }
+    }
+    entries.sort_by_key(|entry| entry.name.to_lowercase());
+    entries
+}
+
+/// Reads the `[Desktop Entry]` group of a desktop file. `None` if the
+/// application should not be listed, or needs a terminal and `terminal` is
+/// `None`.
+pub fn parse(text: &str, terminal: Option<&str>) -> Option<Entry> {
+    let mut fields = HashMap::new();
+    let mut in_group = false;
+    for line in text.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_group = line == "[Desktop Entry]";
+        } else if in_group && !line.starts_with('#') {
+            if let Some((key, value)) = line.split_once('=') {
+                fields
+                    .entry(key.trim())
+                    .or_insert_with(|| unescape(value.trim()));
+            }
+        }
+    }
+
+    let flag = |key| fields.get(key).is_some_and(|value| value == "true");
+    let application = fields.get("Type").is_none_or(|kind| kind == "Application");
+    if !application || flag("NoDisplay") || flag("Hidden") {
+        return None;
+    }
+
+    let mut exec = strip_field_codes(fields.get("Exec")?);
+    if flag("Terminal") {
+        exec = format!("{} -e {exec}", terminal?);
+    }
+    let keywords = fields
+        .get("Keywords")
+        .map(|keywords| {
+            keywords
+                .split(';')
+                .filter(|keyword| !keyword.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .filter(|keywords| !keywords.is_empty());
+    // Icon names usually refer to images, so only single glyphs are used.
+    let icon = fields
+        .get("Icon")
+        .filter(|icon| icon.chars().count() == 1)
+        .map_or_else(|| String::from(ICON), String::clone);
+
+    Some(Entry {
+        name: fields.get("Name")?.clone(),
+        icon,
+        keywords,
+        description: fields.get("GenericName").cloned(),
+        key: None,
+        preview: None,
+        source: None,
+        confirm: None,
+        action: Action::Exec(exec),
+    })
+}
+
+/// Replaces the escapes allowed in desktop file strings.
+fn unescape(value: &str) -> String {
+    let mut text = String::new();
+    let mut chars = value.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            text.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => text.push(' '),
+            Some('n') => text.push('\n'),
+            Some('t') => text.push('\t'),
+            Some('r') => text.push('\r'),
+            Some(ch) => text.push(ch),
+            None => text.push('\\'),
+        }
+    }
+    text
+}
+
+/// Removes the `%f`, `%U` and similar codes from an `Exec` value, since
+/// applications are started without files or URLs.
+fn strip_field_codes(exec: &str) -> String {
+    let mut command = String::new();
+    let mut chars = exec.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '%' {
+            command.push(ch);
+        } else if chars.next() == Some('%') {
+            command.push('%');
+        }
+    }
+    command.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exec(entry: &Entry) -> &str {
+        match &entry.action {
+            Action::Exec(exec) => exec,
+            _ => panic!("desktop entries run with exec"),
+        }
+    }
+
+    #[test]
+    fn reads_desktop_entry_group() {
+        let entry = parse(
+            "[Desktop Entry]\n\
+             Type=Application\n\
+             Name=Firefox\n\
+             Name[de]=Feuerfuchs\n\
+             GenericName=Web Browser\n\
+             Keywords=internet;www;\n\
+             Exec=firefox %u\n\
+             \n\
+             [Desktop Action new-window]\n\
+             Name=New Window\n\
+             Exec=firefox --new-window %u\n",
+            None,
+        )
+        .expect("listed");
+        assert_eq!(entry.name, "Firefox");
+        assert_eq!(entry.description.as_deref(), Some("Web Browser"));
+        assert_eq!(entry.keywords.as_deref(), Some("internet www"));
+        assert_eq!(exec(&entry), "firefox");
+    }
+
+    #[test]
+    fn skips_hidden_entries() {
+        let hidden = "[Desktop Entry]\nName=A\nExec=a\nHidden=true\n";
+        let no_display = "[Desktop Entry]\nName=A\nExec=a\nNoDisplay=true\n";
+        let link = "[Desktop Entry]\nType=Link\nName=A\nURL=https://example.com\n";
+        assert!(parse(hidden, None).is_none());
+        assert!(parse(no_display, None).is_none());
+        assert!(parse(link, None).is_none());
+        assert!(parse("[Desktop Entry]\nName=A\nExec=a\nNoDisplay=false\n", None).is_some());
+    }
+
+    #[test]
+    fn strips_field_codes() {
+        assert_eq!(strip_field_codes("gimp %F"), "gimp");
+        assert_eq!(
+            strip_field_codes("vlc --started-from-file %U"),
+            "vlc --started-from-file"
+        );
+        assert_eq!(strip_field_codes("app %f --icon %i %c %k"), "app --icon");
+        assert_eq!(strip_field_codes("printf 100%%"), "printf 100%");
+    }
+
+    #[test]
+    fn wraps_terminal_apps() {
+        let htop = "[Desktop Entry]\nName=Htop\nExec=htop %f\nTerminal=true\n";
+        let entry = parse(htop, Some("foot")).expect("listed");
+        assert_eq!(exec(&entry), "foot -e htop");
+        assert!(parse(htop, None).is_none());
+    }
+
+    #[test]
+    fn unescapes_values() {
+        let entry = parse("[Desktop Entry]\nName=Two\\sWords\\\\\nExec=a\n", None).unwrap();
+        assert_eq!(entry.name, "Two Words\\");
+    }
+}
@@ -0,0 +1,256 @@
+use std::{
+    env, fs,
+    io::{self, Stdout},
+    path::PathBuf,
+};
+
+use glob::Pattern;
+use serde::Deserialize;
+use termion::{
+    event::{Key, MouseButton, MouseEvent},
+    raw::RawTerminal,
+};
+
+use crate::{
+    config::{Action, CommandAction, Confirm, Entry, FilesConfig, ListConfig},
+    keys, output,
+    style::Styled,
+    theme::Theme,
+};
+
+use super::{
+    action::{self, Vars},
+    list::ListApp,
+    App, Instruction, Snapshot,
+};
+
+#[derive(Deserialize)]
+#[serde(try_from = "String")]
+pub struct Glob(Pattern);
+
+impl TryFrom<String> for Glob {
+    type Error = glob::PatternError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Pattern::new(&value).map(Self)
+    }
+}
+
+/// Lists the files in a directory, moving into directories with `accept` or
+/// right and out of them with left.
+pub struct FilesApp {
+    pub(super) dir: PathBuf,
+    pub(super) include: Vec<Glob>,
+    pub(super) exclude: Vec<Glob>,
+    pub(super) hidden: bool,
+    pub(super) open: Action,
+    pub(super) confirm: Option<Confirm>,
+    pub(super) icon: Styled,
+    pub(super) theme: Theme,
+
+    /// The entries of `dir`.
+    pub(super) list: ListApp,
+}
+
+impl FilesApp {
+    pub fn new(config: FilesConfig, icon: Styled, theme: Theme, size: (usize, usize)) -> Self {
+        let list = ListApp::new(
+            ListConfig::default(),
+            Styled::from(String::new()),
+            icon.clone(),
+            theme.clone(),
+            size,
+        );
+        let mut app = Self {
+            dir: action::expand_home(&config.root),
+            include: config.include,
+            exclude: config.exclude,
+            hidden: config.hidden,
+            open: config.open.unwrap_or_else(|| {
+                Action::Command(Box::new(CommandAction {
+                    name: String::from("xdg-open"),
+                    args: vec![String::from("{path}")],
+                    ..Default::default()
+                }))
+            }),
+            confirm: config.confirm,
+            icon,
+            theme,
+            list,
+        };
+        app.load(None);
+        app
+    }
+
+    fn entries(&self) -> Vec<Entry> {
+        let Ok(read) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        let mut files: Vec<_> = read
+            .flatten()
+            .filter_map(|file| {
+                let name = file.file_name().into_string().ok()?;
+                let is_dir = file.path().is_dir();
+                let included = is_dir
+                    || self.include.is_empty()
+                    || self.include.iter().any(|glob| glob.0.matches(&name));
+                let excluded = self.exclude.iter().any(|glob| glob.0.matches(&name))
+                    || (name.starts_with('.') && !self.hidden);
+                (included && !excluded).then_some((is_dir, name))
+            })
+            .collect();
+        // Directories first.
+        files.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+
+        files
+            .into_iter()
+            .map(|(is_dir, name)| Entry {
+                name,
+                icon: String::from(if is_dir { "\u{f07b}" } else { "\u{f15b}" }),
+                keywords: None,
+                description: None,
+                key: None,
+                preview: None,
+                source: None,
+                confirm: None,
+                // Accepting runs `open` with the path instead.
+                action: Action::Print(String::new()),
+            })
+            .collect()
+    }
+
+    /// Lists `dir` again, selecting the entry named `selected` if given.
+    fn load(&mut self, selected: Option<String>) {
+        let dir = self.dir.to_string_lossy().to_string();
+        let dir = match env::var("HOME") {
+            Ok(home) if dir.starts_with(&home) => dir.replacen(&home, "~", 1),
+            _ => dir,
+        };
+
+        self.list = ListApp::new(
+            ListConfig {
+                entries: self.entries(),
+                ..Default::default()
+            },
+            Styled {
+                // Leaves room for the icon and filter.
+                text: output::truncate(&dir, self.list.width.saturating_sub(8)),
+                style: self.theme.placeholder.clone(),
+            },
+            self.icon.clone(),
+            self.theme.clone(),
+            (self.list.width, self.list.height),
+        );
+        self.list.restore(Snapshot {
+            selected,
+            ..Default::default()
+        });
+    }
+
+    fn enter(&mut self, name: &str) {
+        self.dir.push(name);
+        self.load(None);
+    }
+
+    fn leave(&mut self) {
+        let name = self
+            .dir
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string());
+        if self.dir.pop() {
+            self.load(name);
+        }
+    }
+
+    /// Enters the selected directory, or runs the open action on the
+    /// selected file.
+    fn accept(&mut self) -> Instruction {
+        let Some(entry) = self.list.selected_entry() else {
+            return Instruction::None;
+        };
+        let name = entry.name.clone();
+        let path = self.dir.join(&name);
+        if path.is_dir() {
+            self.enter(&name);
+            return Instruction::None;
+        }
+
+        let vars = Vars::default()
+            .with("path", path.to_string_lossy())
+            .with("name", name)
+            .with("dir", self.dir.to_string_lossy())
+            .with("input", &self.list.filter);
+        let question = format!("open {}?", path.display());
+        action::run_confirmed(
+            &self.open,
+            self.confirm.as_ref(),
+            question,
+            &vars,
+            &self.theme,
+        )
+    }
+}
+
+impl App for FilesApp {
+    fn draw(&self, terminal: &mut RawTerminal<Stdout>) -> io::Result<()> {
+        self.list.draw(terminal)
+    }
+
+    fn handle_input(&mut self, key: Key) -> Instruction {
+        match key {
+            Key::Right if self.list.selected_entry().is_some() => {
+                let name = self.list.selected_entry().map(|entry| entry.name.clone());
+                if let Some(name) = name.filter(|name| self.dir.join(name).is_dir()) {
+                    self.enter(&name);
+                }
+                Instruction::None
+            }
+            Key::Left => {
+                self.leave();
+                Instruction::None
+            }
+            Key::Alt(digit @ '1'..='9') => {
+                let n = digit as usize - '1' as usize;
+                if n >= self.list.list.len() {
+                    return Instruction::None;
+                }
+                self.list.select(n);
+                self.accept()
+            }
+            key => self.list.handle_input(key),
+        }
+    }
+
+    fn handle_command(&mut self, command: keys::Command) -> Instruction {
+        match command {
+            keys::Command::Accept => self.accept(),
+            keys::Command::AcceptAlt => match self.accept() {
+                Instruction::Quit => Instruction::None,
+                instruction => instruction,
+            },
+            keys::Command::ToggleHidden => {
+                self.hidden = !self.hidden;
+                let selected = self.list.selected_entry().map(|entry| entry.name.clone());
+                self.load(selected);
+                Instruction::None
+            }
+            command => self.list.handle_command(command),
+        }
+    }
+
+    fn handle_mouse(&mut self, event: MouseEvent) -> Instruction {
+        match event {
+            // A second click on the same entry accepts it.
+            MouseEvent::Press(MouseButton::Left, x, y)
+                if self.list.result_at(x as usize, y as usize) == Some(self.list.selected) =>
+            {
+                self.accept()
+            }
+            event => self.list.handle_mouse(event),
+        }
+    }
+
+    fn handle_resize(&mut self, width: usize, height: usize) {
+        self.list.handle_resize(width, height);
+    }
+}
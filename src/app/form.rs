@@ -0,0 +1,258 @@
+use std::io::{self, Stdout, Write};
+
+use termion::{
+    event::{Key, MouseButton, MouseEvent},
+    raw::RawTerminal,
+};
+
+use crate::{
+    config::{Action, Confirm, FieldKind, FormField},
+    keys,
+    output::Output,
+    style::Styled,
+    theme::Theme,
+};
+
+use super::{
+    action::{self, Vars},
+    App, Instruction,
+};
+
+/// The current value of a form field.
+pub enum Value {
+    Text(String),
+    /// The index of the selected option.
+    Choice(usize),
+    Toggle(bool),
+}
+
+pub struct FormApp {
+    pub(super) fields: Vec<FormField>,
+    pub(super) values: Vec<Value>,
+    pub(super) action: Action,
+    pub(super) confirm: Option<Confirm>,
+    pub(super) placeholder: Styled,
+    pub(super) icon: Styled,
+    pub(super) theme: Theme,
+
+    pub(super) focused: usize,
+    /// The field that failed validation, and why.
+    pub(super) error: Option<(usize, String)>,
+
+    pub(super) width: usize,
+    pub(super) height: usize,
+}
+
+impl FormApp {
+    fn label(field: &FormField) -> &str {
+        field.label.as_deref().unwrap_or(&field.name)
+    }
+
+    fn label_width(&self) -> usize {
+        self.fields
+            .iter()
+            .map(|field| Self::label(field).chars().count())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// The value of field `i` as passed to the action.
+    fn text(&self, i: usize) -> String {
+        match (&self.fields[i].kind, &self.values[i]) {
+            (FieldKind::Choice { options, .. }, Value::Choice(option)) => {
+                options.get(*option).cloned().unwrap_or_default()
+            }
+            (_, Value::Text(text)) => text.clone(),
+            (FieldKind::Toggle { on, off, .. }, Value::Toggle(value)) => {
+                String::from(if *value { on } else { off })
+            }
+            (_, Value::Choice(_) | Value::Toggle(_)) => String::new(),
+        }
+    }
+
+    fn vars(&self) -> Vars {
+        self.fields
+            .iter()
+            .enumerate()
+            .fold(Vars::default(), |vars, (i, field)| {
+                let name = format!("field.{}", field.name);
+                match field.kind {
+                    FieldKind::Secret => vars.with_secret(&name, self.text(i)),
+                    _ => vars.with(&name, self.text(i)),
+                }
+            })
+    }
+
+    fn submit(&mut self) -> Instruction {
+        let vars = self.vars();
+        for (i, field) in self.fields.iter().enumerate() {
+            let Some(validator) = &field.validate else {
+                continue;
+            };
+            if let Err(err) = validator.validate(&self.text(i), &vars) {
+                self.focused = i;
+                self.error = Some((i, err));
+                return Instruction::None;
+            }
+        }
+
+        let question = String::from("submit?");
+        action::run_confirmed(
+            &self.action,
+            self.confirm.as_ref(),
+            question,
+            &vars,
+            &self.theme,
+        )
+    }
+
+    fn focus(&mut self, step: isize) {
+        let len = self.fields.len() as isize;
+        if len == 0 {
+            return;
+        }
+        self.focused = (self.focused as isize + step).rem_euclid(len) as usize;
+    }
+
+    /// Moves the focused choice by `step` options, or flips the focused
+    /// toggle.
+    fn cycle(&mut self, step: isize) {
+        let i = self.focused;
+        match (&self.fields[i].kind, &mut self.values[i]) {
+            (FieldKind::Choice { options, .. }, Value::Choice(option)) if !options.is_empty() => {
+                let len = options.len() as isize;
+                *option = (*option as isize + step).rem_euclid(len) as usize;
+            }
+            (_, Value::Toggle(on)) => *on = !*on,
+            _ => (),
+        }
+        self.error = None;
+    }
+}
+
+impl App for FormApp {
+    fn draw(&self, terminal: &mut RawTerminal<Stdout>) -> io::Result<()> {
+        terminal.clear()?;
+        terminal.print(&self.icon)?;
+        terminal.print(" ")?;
+        terminal.print(&self.placeholder)?;
+
+        terminal.move_cursor(1, 2)?;
+        terminal.divider(self.width, &self.theme.divider)?;
+
+        let label_width = self.label_width();
+        let value_col = label_width + 4;
+        let mut cursor = (value_col, 3);
+        for (i, field) in self.fields.iter().enumerate().take(self.height - 2) {
+            let style = if i == self.focused {
+                &self.theme.highlight
+            } else {
+                &self.theme.placeholder
+            };
+            terminal.move_cursor(2, i + 3)?;
+            terminal.print(Styled {
+                text: format!("{:<label_width$}", Self::label(field)),
+                style: style.clone(),
+            })?;
+
+            terminal.move_cursor(value_col, i + 3)?;
+            terminal.print(&self.theme.prompt)?;
+            let value = match (&field.kind, &self.values[i]) {
+                (FieldKind::Secret, Value::Text(text)) => "•".repeat(text.chars().count()),
+                (FieldKind::Choice { .. }, _) => format!("‹ {} ›", self.text(i)),
+                (_, Value::Toggle(on)) => String::from(if *on { "[x]" } else { "[ ]" }),
+                (_, _) => self.text(i),
+            };
+            terminal.print(&value)?;
+            terminal.print(termion::style::Reset)?;
+
+            if i == self.focused {
+                let offset = match self.values[i] {
+                    Value::Text(_) => value.chars().count(),
+                    _ => 0,
+                };
+                cursor = (value_col + offset, i + 3);
+            }
+
+            if let Some((_, error)) = self.error.as_ref().filter(|(field, _)| *field == i) {
+                terminal.print("  ")?;
+                terminal.print(Styled {
+                    text: error.clone(),
+                    style: self.theme.error.clone(),
+                })?;
+            }
+        }
+
+        terminal.move_cursor(cursor.0, cursor.1)?;
+        terminal.flush()
+    }
+
+    fn handle_input(&mut self, key: Key) -> Instruction {
+        let Some(value) = self.values.get_mut(self.focused) else {
+            return Instruction::None;
+        };
+        match (key, value) {
+            (Key::Char(ch), Value::Text(text)) => {
+                text.push(ch);
+                self.error = None;
+            }
+            (Key::Backspace, Value::Text(text)) => {
+                text.pop();
+                self.error = None;
+            }
+            (Key::Char(' '), Value::Toggle(_)) | (Key::Right, _) => self.cycle(1),
+            (Key::Left, _) => self.cycle(-1),
+            _ => (),
+        }
+        Instruction::None
+    }
+
+    fn handle_command(&mut self, command: keys::Command) -> Instruction {
+        match command {
+            keys::Command::Accept => self.submit(),
+            keys::Command::AcceptAlt => match self.submit() {
+                Instruction::Quit => Instruction::None,
+                instruction => instruction,
+            },
+            keys::Command::SelectNext => {
+                self.focus(1);
+                Instruction::None
+            }
+            keys::Command::SelectPrev => {
+                self.focus(-1);
+                Instruction::None
+            }
+            keys::Command::ClearFilter => {
+                if let Some(Value::Text(text)) = self.values.get_mut(self.focused) {
+                    text.clear();
+                }
+                Instruction::None
+            }
+            _ => Instruction::None,
+        }
+    }
+
+    fn handle_mouse(&mut self, event: MouseEvent) -> Instruction {
+        if let MouseEvent::Press(MouseButton::Left, _, y) = event {
+            let Some(i) = (y as usize)
+                .checked_sub(3)
+                .filter(|i| *i < self.fields.len())
+            else {
+                return Instruction::None;
+            };
+
+            // A click on the focused choice or toggle changes it.
+            if i == self.focused {
+                self.cycle(1);
+            } else {
+                self.focused = i;
+            }
+        }
+        Instruction::None
+    }
+
+    fn handle_resize(&mut self, width: usize, height: usize) {
+        self.width = width;
+        self.height = height;
+    }
+}
@@ -1,59 +1,585 @@
-use std::{
-    io::{self, stderr, stdout, Stdout, Write},
-    process::Command,
-    thread,
-    time::Duration,
-};
+use std::io::{self, Stdout, Write};
 
-use termion::{event::Key, raw::RawTerminal};
+use termion::{
+    event::{Key, MouseButton, MouseEvent},
+    raw::RawTerminal,
+};
 
 use crate::{
-    config::{Action, Config, Entry},
-    output::Output,
-    style::{Color, Style, Styled},
+    config::{Action, Confirm, Entry, Fallback, ListConfig},
+    keys, matcher,
+    output::{self, Output},
+    style::{Style, Styled},
+    theme::Theme,
     App,
 };
 
-use super::{message::MessageApp, Instruction};
+use super::{
+    action::{self, Vars},
+    preview::{self, Position, Preview},
+    Instruction, Snapshot,
+};
 
 pub struct ListApp {
     pub(super) entries: Vec<Entry>,
+    /// The index of the first entry in each group, and its title.
+    pub(super) groups: Vec<(usize, String)>,
+    pub(super) search_groups: bool,
+    pub(super) hotkey_mode: bool,
+    pub(super) multi: bool,
+    /// Run once for all marked entries in multi mode, instead of their own
+    /// actions.
+    pub(super) batch: Option<Action>,
+    pub(super) batch_confirm: Option<Confirm>,
     pub(super) placeholder: Styled,
     pub(super) icon: Styled,
     pub(super) selected_style: Style,
+    pub(super) highlight_style: Style,
+    pub(super) theme: Theme,
+    pub(super) preview_command: Option<String>,
+    pub(super) preview: Option<Preview>,
 
     pub(super) filter: String,
     pub(super) selected: usize,
-    pub(super) list: Vec<usize>,
+    pub(super) list: Vec<Match>,
+    /// Rows listed after the matches while filtering. Result `i` past the
+    /// end of `list` is fallback `i - list.len()`.
+    pub(super) fallbacks: Vec<Fallback>,
+    /// Entries marked in multi mode, in the order they were marked.
+    pub(super) marked: Vec<usize>,
+    /// The first visible row.
+    pub(super) scroll: usize,
 
     pub(super) width: usize,
     pub(super) height: usize,
 }
 
+pub(super) struct Match {
+    pub(super) entry: usize,
+    pub(super) field: Field,
+    /// Char positions of the filter in the matched field.
+    pub(super) positions: Vec<usize>,
+}
+
+pub(super) enum Field {
+    Name,
+    /// The keyword the filter was found in.
+    Keyword(String),
+    Description,
+    /// The title of the entry's group.
+    Group(String),
+}
+
+impl Field {
+    /// Matches in fields with a lower weight are listed first.
+    fn weight(&self) -> u8 {
+        match self {
+            Field::Name => 0,
+            Field::Keyword(_) => 1,
+            Field::Description => 2,
+            Field::Group(_) => 3,
+        }
+    }
+}
+
+enum Row {
+    Header(usize),
+    Item(usize),
+}
+
 impl ListApp {
-    fn update_list(&mut self) {
-        self.list = self
+    pub fn new(
+        list: ListConfig,
+        placeholder: Styled,
+        icon: Styled,
+        theme: Theme,
+        (width, height): (usize, usize),
+    ) -> Self {
+        let ListConfig {
+            mut entries,
+            entries_from: _,
+            hide: _,
+            terminal: _,
+            groups,
+            search_groups,
+            hotkey_mode,
+            multi,
+            batch,
+            batch_confirm,
+            selected_style,
+            highlight_style,
+            preview,
+            preview_position,
+            fallback,
+            mut fallbacks,
+        } = list;
+        fallbacks.extend(fallback);
+        let groups = groups
+            .into_iter()
+            .map(|group| {
+                let start = entries.len();
+                entries.extend(group.entries);
+                (start, group.title)
+            })
+            .collect();
+        let has_preview = preview.is_some() || entries.iter().any(|e| e.preview.is_some());
+
+        let mut app = Self {
+            entries,
+            groups,
+            search_groups,
+            hotkey_mode,
+            multi,
+            batch,
+            batch_confirm,
+            placeholder,
+            selected_style: selected_style.unwrap_or_else(|| theme.selected_entry.clone()),
+            highlight_style: highlight_style.unwrap_or_else(|| theme.highlight.clone()),
+            icon,
+            theme,
+            preview_command: preview,
+            preview: has_preview.then(|| Preview::new(preview_position)),
+
+            filter: String::new(),
+            selected: 0,
+            list: Vec::new(),
+            fallbacks,
+            marked: Vec::new(),
+            scroll: 0,
+
+            width,
+            height,
+        };
+        app.update_list();
+        app.select(0);
+        app
+    }
+
+    pub(super) fn update_list(&mut self) {
+        let mut list: Vec<_> = self
             .entries
             .iter()
             .enumerate()
-            .filter_map(|(i, Entry { name, keywords, .. })| {
-                if name.contains(&self.filter)
-                    || keywords.as_ref().is_some_and(|s| s.contains(&self.filter))
-                {
-                    Some(i)
-                } else {
-                    None
-                }
-            })
-            .take(self.height - 2)
+            .filter_map(|(i, entry)| self.match_entry(i, entry))
             .collect();
+        list.sort_by_key(|item| item.field.weight());
+        self.list = list;
+    }
+
+    /// The number of matches and fallbacks shown.
+    fn result_count(&self) -> usize {
+        if self.filter.is_empty() {
+            self.list.len()
+        } else {
+            self.list.len() + self.fallbacks.len()
+        }
+    }
+
+    /// Selects the `i`th result, scrolling it into view.
+    pub(super) fn select(&mut self, i: usize) {
+        self.selected = i;
+
+        let visible = self.list_height();
+        let row = self
+            .rows()
+            .iter()
+            .position(|row| matches!(row, Row::Item(item) if *item == i))
+            .unwrap_or(0);
+        if row < self.scroll {
+            self.scroll = row;
+        } else if row >= self.scroll + visible {
+            self.scroll = row + 1 - visible;
+        }
+
+        self.update_preview();
+    }
+
+    /// The selected entry, unless a fallback or nothing is selected.
+    pub(super) fn selected_entry(&self) -> Option<&Entry> {
+        let item = self.list.get(self.selected)?;
+        Some(&self.entries[item.entry])
+    }
+
+    /// The result drawn at column `col` of screen line `ln`, outside the
+    /// preview.
+    pub(super) fn result_at(&self, col: usize, ln: usize) -> Option<usize> {
+        if col > self.list_width() {
+            return None;
+        }
+        let ln = ln.checked_sub(3).filter(|ln| *ln < self.list_height())?;
+        let row = self.rows().into_iter().nth(self.scroll + ln)?;
+        match row {
+            Row::Item(i) => Some(i),
+            Row::Header(_) => None,
+        }
+    }
+
+    fn shows_preview(&self) -> bool {
+        self.preview.as_ref().is_some_and(|preview| preview.visible)
+    }
+
+    fn preview_position(&self) -> Option<Position> {
+        Some(
+            self.preview
+                .as_ref()
+                .filter(|preview| preview.visible)?
+                .position,
+        )
+    }
+
+    fn list_width(&self) -> usize {
+        match self.preview_position() {
+            Some(Position::Right) => self.width / 2,
+            _ => self.width,
+        }
+    }
+
+    fn list_height(&self) -> usize {
+        match self.preview_position() {
+            Some(Position::Bottom) => (self.height - 2) / 2,
+            _ => self.height - 2,
+        }
+    }
+
+    /// The selected entry and its expanded preview command.
+    fn preview_key(&self) -> Option<preview::Key> {
+        let item = self.list.get(self.selected)?;
+        let entry = &self.entries[item.entry];
+        let template = entry.preview.as_ref().or(self.preview_command.as_ref())?;
+
+        let vars = Vars::default()
+            .with("name", &entry.name)
+            .with("input", &self.filter);
+        Some((item.entry, vars.expand_shell(template)))
+    }
+
+    fn update_preview(&mut self) {
+        if !self.shows_preview() {
+            return;
+        }
+        let Some(key) = self.preview_key() else {
+            return;
+        };
+        if let Some(preview) = &mut self.preview {
+            preview.request(key);
+        }
+    }
+
+    fn group_of(&self, entry: usize) -> Option<usize> {
+        self.groups.iter().rposition(|(start, _)| *start <= entry)
+    }
+
+    /// Lays out the list with a header before each group. Headers are
+    /// collapsed while filtering, since results are no longer in order.
+    fn rows(&self) -> Vec<Row> {
+        let mut rows = Vec::new();
+        let mut group = None;
+        for (i, item) in self.list.iter().enumerate() {
+            if self.filter.is_empty() {
+                let next = self.group_of(item.entry);
+                if next != group {
+                    if let Some(next) = next {
+                        rows.push(Row::Header(next));
+                    }
+                    group = next;
+                }
+            }
+            rows.push(Row::Item(i));
+        }
+        rows.extend((self.list.len()..self.result_count()).map(Row::Item));
+        rows
+    }
+
+    fn match_entry(&self, entry: usize, item: &Entry) -> Option<Match> {
+        if let Some(positions) = matcher::find(&item.name, &self.filter) {
+            return Some(Match {
+                entry,
+                field: Field::Name,
+                positions,
+            });
+        }
+        if let Some(keyword) = item
+            .keywords
+            .as_ref()
+            .and_then(|keywords| matcher::find_keyword(keywords, &self.filter))
+        {
+            return Some(Match {
+                entry,
+                field: Field::Keyword(keyword.to_string()),
+                positions: Vec::new(),
+            });
+        }
+
+        if let Some(positions) = item
+            .description
+            .as_ref()
+            .and_then(|description| matcher::find(description, &self.filter))
+        {
+            return Some(Match {
+                entry,
+                field: Field::Description,
+                positions,
+            });
+        }
+
+        let group = self.group_of(entry).filter(|_| self.search_groups)?;
+        let title = &self.groups[group].1;
+        matcher::find(title, &self.filter)?;
+        Some(Match {
+            entry,
+            field: Field::Group(title.clone()),
+            positions: Vec::new(),
+        })
+    }
+}
+
+impl ListApp {
+    fn draw_item(&self, terminal: &mut RawTerminal<Stdout>, ln: usize, i: usize) -> io::Result<()> {
+        let Some(item) = self.list.get(i) else {
+            return self.draw_fallback(terminal, i);
+        };
+        let entry = &self.entries[item.entry];
+        let style = if i == self.selected {
+            &self.selected_style
+        } else {
+            &self.theme.entry
+        };
+
+        let name_positions = match item.field {
+            Field::Name => &item.positions[..],
+            _ => &[],
+        };
+
+        if self.marked.contains(&item.entry) {
+            terminal.print(Styled {
+                text: String::from("● "),
+                style: self.highlight_style.clone(),
+            })?;
+        } else if i < 9 {
+            // Quick-select hint for alt+1..9.
+            terminal.print(Styled {
+                text: format!("{} ", i + 1),
+                style: self.theme.placeholder.clone(),
+            })?;
+        } else {
+            terminal.print("  ")?;
+        }
+        terminal.print(style)?;
+        terminal.print(&entry.icon)?;
+        terminal.print(" ")?;
+        terminal.highlighted(&entry.name, name_positions, style, &self.highlight_style)?;
+        terminal.print(termion::style::Reset)?;
+        let mut used = 3 + entry.icon.chars().count() + entry.name.chars().count();
+
+        // Group headers show the source menu until filtering collapses them.
+        if let Some(source) = entry.source.as_ref().filter(|_| !self.filter.is_empty()) {
+            let tag = format!(" [{source}]");
+            used += tag.chars().count();
+            terminal.print(Styled {
+                text: tag,
+                style: self.theme.placeholder.clone(),
+            })?;
+        }
+
+        if let Some(chord) = entry.key {
+            let hint = format!(" [{chord}]");
+            used += hint.chars().count();
+            terminal.print(Styled {
+                text: hint,
+                style: self.theme.placeholder.clone(),
+            })?;
+        }
+
+        if let Field::Keyword(hidden) | Field::Group(hidden) = &item.field {
+            let hint = format!(" (matched: {hidden})");
+            used += hint.chars().count();
+            terminal.print(Styled {
+                text: hint,
+                style: self.theme.placeholder.clone(),
+            })?;
+        }
+
+        // Right-align the description, leaving a gap after the name.
+        if let Some(description) = &entry.description {
+            let available = self.list_width().saturating_sub(used + 3);
+            if available < 2 {
+                return Ok(());
+            }
+
+            let description = output::truncate(description, available);
+            let positions = match item.field {
+                Field::Description => &item.positions[..],
+                _ => &[],
+            };
+            let style = &self.theme.placeholder;
+
+            terminal.move_cursor(self.list_width() - description.chars().count(), ln)?;
+            terminal.print(style)?;
+            terminal.highlighted(&description, positions, style, &self.highlight_style)?;
+            terminal.print(termion::style::Reset)?;
+        }
+
+        Ok(())
+    }
+
+    fn draw_fallback(&self, terminal: &mut RawTerminal<Stdout>, i: usize) -> io::Result<()> {
+        let fallback = &self.fallbacks[i - self.list.len()];
+        let style = if i == self.selected {
+            &self.selected_style
+        } else {
+            &self.theme.entry
+        };
+
+        if i < 9 {
+            terminal.print(Styled {
+                text: format!("{} ", i + 1),
+                style: self.theme.placeholder.clone(),
+            })?;
+        } else {
+            terminal.print("  ")?;
+        }
+        terminal.print(style)?;
+        terminal.print(&fallback.icon)?;
+        terminal.print(" ")?;
+        terminal.print(fallback.name.replace("{input}", &self.filter))?;
+        terminal.print(termion::style::Reset)
+    }
+}
+
+impl ListApp {
+    fn vars(&self, entry: usize) -> Vars {
+        Vars::default()
+            .with("name", &self.entries[entry].name)
+            .with("input", &self.filter)
+    }
+
+    fn accept(&self, entry: usize) -> Instruction {
+        let Entry {
+            name,
+            confirm,
+            action,
+            ..
+        } = &self.entries[entry];
+        let question = format!("run {name}?");
+        action::run_confirmed(
+            action,
+            confirm.as_ref(),
+            question,
+            &self.vars(entry),
+            &self.theme,
+        )
+    }
+
+    /// Accepts the `i`th result, either an entry or a fallback.
+    fn accept_result(&self, i: usize) -> Instruction {
+        match self.list.get(i) {
+            Some(item) => self.accept(item.entry),
+            None => {
+                let fallback = &self.fallbacks[i - self.list.len()];
+                let vars = Vars::default().with("input", &self.filter);
+                let question = format!("{}?", vars.expand(&fallback.name));
+                action::run_confirmed(
+                    &fallback.action,
+                    fallback.confirm.as_ref(),
+                    question,
+                    &vars,
+                    &self.theme,
+                )
+            }
+        }
+    }
+
+    /// Runs the batch action once for all marked entries, or each marked
+    /// entry's own action in the order they were marked. Falls back to the
+    /// selected entry if none are marked.
+    fn accept_marked(&self) -> Instruction {
+        let mut marked = self.marked.clone();
+        if marked.is_empty() && self.selected >= self.list.len() {
+            return self.accept_result(self.selected);
+        }
+        if marked.is_empty() {
+            marked.extend(self.list.get(self.selected).map(|item| item.entry));
+        }
+
+        if let Some(batch) = &self.batch {
+            let names = marked.iter().map(|i| self.entries[*i].name.clone());
+            let vars = Vars::default()
+                .with("input", &self.filter)
+                .with_selected(names.collect());
+            let question = format!("run on {} entries?", marked.len());
+            return action::run_confirmed(
+                batch,
+                self.batch_confirm.as_ref(),
+                question,
+                &vars,
+                &self.theme,
+            );
+        }
+
+        if marked.len() > 1 {
+            let unbatchable = marked.iter().map(|i| &self.entries[*i]).find(|entry| {
+                entry
+                    .confirm
+                    .as_ref()
+                    .is_some_and(|confirm| confirm.question(String::new()).is_some())
+                    || !entry.action.batchable()
+            });
+            if let Some(entry) = unbatchable {
+                let message = format!("{} can't run with other marked entries", entry.name);
+                return action::error(message, &self.theme);
+            }
+        }
+
+        // Every entry runs, even after one fails.
+        let mut printed = Vec::new();
+        let mut failure = None;
+        for entry in marked {
+            match self.accept(entry) {
+                Instruction::Print(lines) => printed.extend(lines),
+                Instruction::None | Instruction::Quit => (),
+                instruction => {
+                    failure.get_or_insert(instruction);
+                }
+            }
+        }
+
+        if let Some(failure) = failure {
+            failure
+        } else if printed.is_empty() {
+            Instruction::Quit
+        } else {
+            Instruction::Print(printed)
+        }
+    }
+
+    fn toggle_mark(&mut self) {
+        let Some(item) = self.list.get(self.selected) else {
+            return;
+        };
+        match self.marked.iter().position(|entry| *entry == item.entry) {
+            Some(i) => {
+                self.marked.remove(i);
+            }
+            None => self.marked.push(item.entry),
+        }
+    }
+
+    /// Finds the entry bound to `key`. Plain characters only trigger entries
+    /// in hotkey mode, since they would otherwise be typed into the filter.
+    fn hotkey(&self, key: Key) -> Option<usize> {
+        if matches!(key, Key::Char(_)) && !self.hotkey_mode {
+            return None;
+        }
+        self.entries
+            .iter()
+            .position(|entry| entry.key.is_some_and(|chord| chord.0 == key))
     }
 }
 
 impl App for ListApp {
     fn draw(&self, terminal: &mut RawTerminal<Stdout>) -> io::Result<()> {
-        let msg_width = self.placeholder.len() + 2;
-        let prompt_offset = self.width / 2 - msg_width / 2;
+        let msg_width = self.placeholder.chars().count() + 2;
+        let prompt_offset = (self.width / 2).saturating_sub(msg_width / 2);
 
         terminal.clear()?;
         terminal.print(" ".repeat(prompt_offset))?;
@@ -63,119 +589,163 @@ impl App for ListApp {
         if self.filter.is_empty() {
             terminal.print(&self.placeholder)?;
         } else {
+            terminal.print(&self.theme.prompt)?;
             terminal.print(&self.filter)?;
+            terminal.print(termion::style::Reset)?;
+        }
+
+        if !self.marked.is_empty() {
+            let count = format!("{} marked", self.marked.len());
+            terminal.move_cursor(self.width - count.len(), 1)?;
+            terminal.print(Styled {
+                text: count,
+                style: self.theme.placeholder.clone(),
+            })?;
         }
 
         terminal.move_cursor(1, 2)?;
-        terminal.print("\x1b[38;5;235m")?;
-        terminal.print("─".repeat(self.width))?;
-        terminal.print("\x1b[0m")?;
+        terminal.divider(self.width, &self.theme.divider)?;
 
-        for (i, entry) in self
-            .list
+        let rows = self.rows();
+        for (ln, row) in rows
             .iter()
-            .map(|i| &self.entries[*i])
-            .take(self.height - 2)
+            .skip(self.scroll)
+            .take(self.list_height())
             .enumerate()
         {
-            terminal.move_cursor(1, i + 3)?;
-            terminal.print("  ")?;
-
-            if i == self.selected {
-                terminal.print(&self.selected_style)?;
-            }
-            terminal.print(&entry.icon)?;
-            terminal.print(" ")?;
-            terminal.print(&entry.name)?;
-            if i == self.selected {
-                terminal.print(termion::style::Reset)?;
+            terminal.move_cursor(1, ln + 3)?;
+            match row {
+                Row::Header(group) => {
+                    terminal.print(" ")?;
+                    terminal.print(&self.theme.header)?;
+                    terminal.print(&self.groups[*group].1)?;
+                    terminal.print(termion::style::Reset)?;
+                }
+                Row::Item(i) => self.draw_item(terminal, ln + 3, *i)?,
             }
         }
 
+        if let (Some(preview), Some(position)) = (&self.preview, self.preview_position()) {
+            let key = self.preview_key();
+            let area = match position {
+                Position::Right => {
+                    let col = self.list_width() + 1;
+                    for ln in 3..=self.height {
+                        terminal.move_cursor(col, ln)?;
+                        terminal.print(&self.theme.divider)?;
+                        terminal.print("│")?;
+                        terminal.print(termion::style::Reset)?;
+                    }
+                    ((col + 2, 3), (self.width - col - 2, self.height - 2))
+                }
+                Position::Bottom => {
+                    let ln = self.list_height() + 3;
+                    terminal.move_cursor(1, ln)?;
+                    terminal.divider(self.width, &self.theme.divider)?;
+                    ((2, ln + 1), (self.width - 2, self.height - ln))
+                }
+            };
+            preview.draw(terminal, key.as_ref(), area.0, area.1, &self.theme)?;
+        }
+
         terminal.move_cursor(prompt_offset + self.filter.len() + 3, 1)?;
 
         terminal.flush()
     }
 
     fn handle_input(&mut self, key: Key) -> Instruction {
+        if let Some(entry) = self.hotkey(key) {
+            return self.accept(entry);
+        }
+
         match key {
-            Key::Char('\n') => {
-                let item = &self.entries[self.list[self.selected]];
-                match &item.action {
-                    Action::Exec(name) => {
-                        let output = Command::new("hyprctl")
-                            .args(["dispatch", "exec"])
-                            .arg(name)
-                            .output();
-                        if let Err(err) = output {
-                            let mut msg = Styled::from(format!("{err}"));
-                            msg.style.fg = Color::Red;
-
-                            Instruction::SetApp(Box::new(MessageApp(msg)))
-                        } else {
-                            Instruction::Quit
-                        }
-                    }
-                    Action::Command {
-                        name,
-                        args,
-                        hold_output,
-                        output_size,
-                    } => {
-                        let mut command = Command::new(name);
-                        command.args(args);
-                        if *hold_output {
-                            if let Some((w, h)) = output_size {
-                                Command::new("hyprctl")
-                                    .args([
-                                        "--batch",
-                                        &format!("dispatch resizeactive exact {w} {h}; dispatch centerwindow"),
-                                    ])
-                                    .output()
-                                    .expect("failed to resize window");
-                                thread::sleep(Duration::from_millis(100));
-                            }
-
-                            command.stdout(stdout()).stderr(stderr());
-                            Instruction::HoldOutput(command)
-                        } else {
-                            let _ = command.output();
-                            Instruction::Quit
-                        }
-                    }
-                    Action::OpenMenu(name) => {
-                        Instruction::SetApp(super::from_config(Config::get_menu(name.to_string())))
-                    }
+            Key::Alt(digit @ '1'..='9') => {
+                let n = digit as usize - '1' as usize;
+                if n < self.result_count() {
+                    self.accept_result(n)
+                } else {
+                    Instruction::None
                 }
             }
+            Key::Backspace => {
+                self.filter.pop();
+                self.update_list();
+                self.select(0);
+                Instruction::None
+            }
+            Key::Char(ch) => {
+                self.filter.push(ch);
+                self.update_list();
+                self.select(0);
+                Instruction::None
+            }
+
+            _ => Instruction::None,
+        }
+    }
+
+    fn handle_command(&mut self, command: keys::Command) -> Instruction {
+        let last = self.result_count().saturating_sub(1);
+        let page = self.list_height();
 
-            Key::Up | Key::BackTab => {
+        match command {
+            keys::Command::Accept | keys::Command::AcceptAlt => {
+                let instruction = if self.multi {
+                    self.accept_marked()
+                } else if self.selected < self.result_count() {
+                    self.accept_result(self.selected)
+                } else {
+                    return Instruction::None;
+                };
+                match instruction {
+                    Instruction::Quit if command == keys::Command::AcceptAlt => Instruction::None,
+                    instruction => instruction,
+                }
+            }
+
+            keys::Command::SelectPrev => {
                 if self.selected > 0 {
-                    self.selected -= 1
+                    self.select(self.selected - 1);
                 } else {
-                    self.selected = self.list.len() - 1
+                    self.select(last);
                 }
                 Instruction::None
             }
-            Key::Down | Key::Char('\t') => {
-                if self.selected < self.list.len() - 1 {
-                    self.selected += 1;
+            keys::Command::SelectNext => {
+                if self.selected < last {
+                    self.select(self.selected + 1);
                 } else {
-                    self.selected = 0;
+                    self.select(0);
                 }
                 Instruction::None
             }
+            keys::Command::PageUp => {
+                self.select(self.selected.saturating_sub(page));
+                Instruction::None
+            }
+            keys::Command::PageDown => {
+                self.select((self.selected + page).min(last));
+                Instruction::None
+            }
 
-            Key::Backspace => {
-                self.selected = 0;
-                self.filter.pop();
+            keys::Command::ClearFilter => {
+                self.filter.clear();
                 self.update_list();
+                self.select(0);
                 Instruction::None
             }
-            Key::Char(ch) => {
-                self.selected = 0;
-                self.filter.push(ch);
-                self.update_list();
+
+            keys::Command::ToggleMark if self.multi => {
+                self.toggle_mark();
+                self.select((self.selected + 1).min(last));
+                Instruction::None
+            }
+
+            keys::Command::TogglePreview => {
+                if let Some(preview) = &mut self.preview {
+                    preview.visible = !preview.visible;
+                }
+                self.select(self.selected);
                 Instruction::None
             }
 
@@ -183,8 +753,63 @@ impl App for ListApp {
         }
     }
 
+    fn handle_mouse(&mut self, event: MouseEvent) -> Instruction {
+        match event {
+            MouseEvent::Press(MouseButton::Left, x, y) => {
+                let Some(i) = self.result_at(x as usize, y as usize) else {
+                    return Instruction::None;
+                };
+
+                // A second click on the same entry accepts it.
+                if i == self.selected {
+                    self.accept_result(i)
+                } else {
+                    self.select(i);
+                    Instruction::None
+                }
+            }
+            MouseEvent::Press(MouseButton::WheelUp, _, _) => {
+                self.scroll = self.scroll.saturating_sub(1);
+                Instruction::None
+            }
+            MouseEvent::Press(MouseButton::WheelDown, _, _) => {
+                let max = self.rows().len().saturating_sub(self.list_height());
+                self.scroll = (self.scroll + 1).min(max);
+                Instruction::None
+            }
+            _ => Instruction::None,
+        }
+    }
+
     fn handle_resize(&mut self, width: usize, height: usize) {
         self.width = width;
         self.height = height;
+        self.select(self.selected);
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            input: self.filter.clone(),
+            cursor: self.filter.len(),
+            selected: self
+                .list
+                .get(self.selected)
+                .map(|item| self.entries[item.entry].name.clone()),
+            ..Default::default()
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.filter = snapshot.input;
+        self.update_list();
+        let selected = snapshot
+            .selected
+            .and_then(|name| {
+                self.list
+                    .iter()
+                    .position(|item| self.entries[item.entry].name == name)
+            })
+            .unwrap_or(0);
+        self.select(selected);
     }
 }
@@ -2,7 +2,7 @@ use std::io::{self, Stdout, Write};
 
 use termion::raw::RawTerminal;
 
-use crate::{output::Output, style::Styled};
+use crate::{keys, output::Output, style::Styled};
 
 use super::{App, Instruction};
 
@@ -16,7 +16,10 @@ impl App for MessageApp {
     }
 
     fn handle_input(&mut self, _key: termion::event::Key) -> Instruction {
-        Instruction::None
+        Instruction::Quit
+    }
+    fn handle_command(&mut self, _command: keys::Command) -> Instruction {
+        Instruction::Quit
     }
     fn handle_resize(&mut self, _width: usize, _height: usize) {}
 }
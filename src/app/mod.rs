@@ -3,33 +3,153 @@ use std::{
     process::Command,
 };
 
+use files::FilesApp;
+use form::{FormApp, Value};
 use list::ListApp;
 use prompt::PromptApp;
-use termion::{event::Key, raw::RawTerminal, terminal_size};
+use tabs::{Tab, TabsApp};
+use termion::{
+    event::{Key, MouseEvent},
+    raw::RawTerminal,
+    terminal_size,
+};
 
 use crate::{
-    config::{Config, MenuConfig},
+    config::{self, Config, Entry, Fallback, FieldKind, Group, ListConfig, MenuConfig},
+    keys::{self, Keymap},
     style::Styled,
 };
 
+pub mod action;
+pub mod complete;
+pub mod confirm;
+pub mod desktop;
+pub mod files;
+pub mod form;
 pub mod list;
 pub mod message;
+pub mod preview;
 pub mod prompt;
+pub mod source;
+pub mod tabs;
+pub mod validate;
+pub mod windows;
 
 pub trait App {
     fn draw(&self, terminal: &mut RawTerminal<Stdout>) -> io::Result<()>;
     fn handle_input(&mut self, key: Key) -> Instruction;
+    fn handle_command(&mut self, _command: keys::Command) -> Instruction {
+        Instruction::None
+    }
+    fn handle_mouse(&mut self, _event: MouseEvent) -> Instruction {
+        Instruction::None
+    }
     fn handle_resize(&mut self, width: usize, height: usize);
+
+    /// Bindings to use instead of the menu's, for apps hosting other menus.
+    fn keymap(&self) -> Option<&Keymap> {
+        None
+    }
+
+    /// State to carry over when the app is rebuilt from a reloaded config.
+    fn snapshot(&self) -> Snapshot {
+        Snapshot::default()
+    }
+    fn restore(&mut self, _snapshot: Snapshot) {}
 }
 
 pub enum Instruction {
     None,
     Quit,
     SetApp(Box<dyn App>),
-    HoldOutput(Command),
+    OpenMenu(String),
+    /// Opens a menu that has no file, so it is not reloaded.
+    OpenConfig(Box<Config>),
+    /// Opens an app over the current menu, keeping its keys.
+    Push(Box<dyn App>),
+    /// Returns to the previous menu, or quits from the first one.
+    Back,
+    /// Quits, then runs the command with the given stdin and waits for a
+    /// key.
+    HoldOutput(Box<action::Process>),
+    /// Quits, then prints each line to stdout.
+    Print(Vec<String>),
+}
+
+#[derive(Default)]
+pub struct Snapshot {
+    pub input: String,
+    pub cursor: usize,
+    pub selected: Option<String>,
+    pub tab: usize,
+}
+
+/// Collects the entries of list menus, grouped by menu, and turns prompt
+/// menus into fallback rows.
+fn combine(menus: &[String]) -> Result<ListConfig, config::Error> {
+    let mut combined = ListConfig {
+        search_groups: true,
+        ..Default::default()
+    };
+
+    for name in menus {
+        let config = Config::load(name).map_err(|err| err.in_menu(name))?;
+        match config.menu {
+            MenuConfig::List(mut list) => {
+                source::resolve(&mut list)?;
+                let entries = list
+                    .entries
+                    .into_iter()
+                    .chain(list.groups.into_iter().flat_map(|group| group.entries))
+                    .map(|entry| Entry {
+                        source: Some(name.clone()),
+                        ..entry
+                    })
+                    .collect();
+                combined.groups.push(Group {
+                    title: name.clone(),
+                    entries,
+                });
+            }
+            // A fallback row would show the input and skip its checks.
+            MenuConfig::Prompt {
+                secret: false,
+                validate: None,
+                action,
+                confirm,
+                ..
+            } => combined.fallbacks.push(Fallback {
+                name: format!("search in {name}: {{input}}"),
+                icon: Styled::from(config.icon).text,
+                confirm,
+                action,
+            }),
+            MenuConfig::Prompt { .. }
+            | MenuConfig::Combine { .. }
+            | MenuConfig::Tabs { .. }
+            | MenuConfig::Files(_)
+            | MenuConfig::Form { .. } => {
+                return Err(config::Error::NotCombinable(name.clone()));
+            }
+        }
+    }
+
+    Ok(combined)
+}
+
+pub fn from_config(config: Config) -> Result<Box<dyn App>, config::Error> {
+    let mut open = config.name.iter().cloned().collect();
+    build(config, &mut open)
 }
 
-pub fn from_config(config: Config) -> Box<dyn App> {
+/// Builds the app for a menu inside the menus named in `open`, which it
+/// must not include as tabs.
+fn build(config: Config, open: &mut Vec<String>) -> Result<Box<dyn App>, config::Error> {
+    if let MenuConfig::Combine { menus } = &config.menu {
+        let menu = MenuConfig::List(Box::new(combine(menus)?));
+        return build(Config { menu, ..config }, open);
+    }
+
     if let Some((w, h)) = config.window_size {
         Command::new("hyprctl")
             .args([
@@ -40,37 +160,129 @@ pub fn from_config(config: Config) -> Box<dyn App> {
             .expect("failed to resize window");
     }
 
-    let size = terminal_size().expect("failed to measure size of the terminal");
-
-    match config.menu {
-        MenuConfig::List {
-            entries,
-            selected_style,
-        } => Box::new(ListApp {
-            list: (0..entries.len()).collect(),
+    if let MenuConfig::Tabs { tabs } = &config.menu {
+        // Tab switching bindings from this menu apply in every tab.
+        let switch = config
+            .keys
+            .only(&[keys::Command::NextTab, keys::Command::PrevTab]);
+        let tabs = tabs
+            .iter()
+            .map(|name| {
+                if open.contains(name) {
+                    return Err(config::Error::Cycle(name.clone()));
+                }
+                let tab = Config::load(name).map_err(|err| err.in_menu(name))?;
+                let mut keys = tab.keys.clone();
+                keys.extend(switch.clone());
 
-            entries,
-            placeholder: Styled::from(config.prompt),
-            selected_style,
-            icon: config.icon.into(),
+                open.push(name.clone());
+                let app = build(
+                    Config {
+                        window_size: None,
+                        ..tab
+                    },
+                    open,
+                );
+                open.pop();
+                Ok(Tab {
+                    name: name.clone(),
+                    app: app.map_err(|err| err.in_menu(name))?,
+                    keys,
+                })
+            })
+            .collect::<Result<_, config::Error>>()?;
+        return Ok(Box::new(TabsApp {
+            tabs,
+            current: 0,
+            theme: config.theme,
+        }));
+    }
 
-            filter: String::new(),
-            selected: 0,
+    let size = terminal_size().expect("failed to measure size of the terminal");
+    let theme = config.theme;
 
-            width: size.0 as usize,
-            height: size.1 as usize,
-        }),
-        MenuConfig::Prompt { action, history } => Box::new(PromptApp {
+    let app: Box<dyn App> = match config.menu {
+        MenuConfig::List(mut list) => {
+            source::resolve(&mut list)?;
+            Box::new(ListApp::new(
+                *list,
+                config.prompt.or_style(&theme.placeholder),
+                config.icon.or_style(&theme.icon),
+                theme,
+                (size.0 as usize, size.1 as usize),
+            ))
+        }
+        MenuConfig::Prompt {
             action,
-            placeholder: Styled::from(config.prompt),
-            icon: config.icon.into(),
+            history,
+            completer,
+            confirm,
+            validate,
+            secret,
+        } => Box::new(PromptApp {
+            action,
+            placeholder: config.prompt.or_style(&theme.placeholder),
+            icon: config.icon.or_style(&theme.icon),
+            theme,
 
             history: if history { Some(Vec::new()) } else { None },
+            history_scroll: 0,
+            completer,
+            completion: None,
+            confirm,
+            validator: validate,
+            secret,
+            error: None,
             input: String::new(),
             cursor_index: 0,
 
             width: size.0 as usize,
             height: size.1 as usize,
         }),
-    }
+        MenuConfig::Files(files) => Box::new(FilesApp::new(
+            *files,
+            config.icon.or_style(&theme.icon),
+            theme,
+            (size.0 as usize, size.1 as usize),
+        )),
+        MenuConfig::Form {
+            fields,
+            action,
+            confirm,
+        } => {
+            let values = fields
+                .iter()
+                .map(|field| match &field.kind {
+                    FieldKind::Text { default } => Value::Text(default.clone()),
+                    FieldKind::Secret => Value::Text(String::new()),
+                    FieldKind::Choice { options, default } => Value::Choice(
+                        default
+                            .as_ref()
+                            .and_then(|default| options.iter().position(|o| o == default))
+                            .unwrap_or(0),
+                    ),
+                    FieldKind::Toggle { default, .. } => Value::Toggle(*default),
+                })
+                .collect();
+            Box::new(FormApp {
+                fields,
+                values,
+                action,
+                confirm,
+                placeholder: config.prompt.or_style(&theme.placeholder),
+                icon: config.icon.or_style(&theme.icon),
+                theme,
+
+                focused: 0,
+                error: None,
+
+                width: size.0 as usize,
+                height: size.1 as usize,
+            })
+        }
+        MenuConfig::Combine { .. } | MenuConfig::Tabs { .. } => {
+            unreachable!("combined and tabbed menus are handled above")
+        }
+    };
+    Ok(app)
 }
@@ -0,0 +1,170 @@
+use std::{
+    collections::HashMap,
+    io::{self, Stdout},
+    os::unix::process::CommandExt,
+    process::{Command, Stdio},
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use serde::Deserialize;
+use termion::raw::RawTerminal;
+
+use crate::{
+    output::{self, Output},
+    theme::Theme,
+};
+
+use super::action;
+
+#[derive(Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Position {
+    #[default]
+    Right,
+    Bottom,
+}
+
+/// An entry and its expanded preview command, which can depend on the
+/// filter.
+pub type Key = (usize, String);
+
+struct Run {
+    key: Key,
+    pid: u32,
+    state: Arc<Mutex<RunState>>,
+}
+
+/// Shared with the thread waiting for the command, which only reaps it
+/// while holding the lock, so a cancelled run never signals a reused pid.
+#[derive(Default)]
+struct RunState {
+    cancelled: bool,
+    exited: bool,
+}
+
+/// Output of a preview command for the selected entry, run in the background
+/// and cached per entry and command.
+pub struct Preview {
+    pub(super) position: Position,
+    pub(super) visible: bool,
+
+    cache: Arc<Mutex<HashMap<Key, String>>>,
+    running: Option<Run>,
+}
+
+impl Preview {
+    pub fn new(position: Position) -> Self {
+        Self {
+            position,
+            visible: true,
+            cache: Arc::default(),
+            running: None,
+        }
+    }
+
+    /// Starts the preview command in `key` unless it is cached or already
+    /// running, cancelling the previous preview.
+    pub fn request(&mut self, key: Key) {
+        if self.cache.lock().unwrap().contains_key(&key) {
+            self.cancel();
+            return;
+        }
+        if self.running.as_ref().is_some_and(|run| run.key == key) {
+            return;
+        }
+        self.cancel();
+
+        let mut child = match Command::new("sh")
+            .args(["-c", &key.1])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .process_group(0)
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(err) => {
+                self.cache.lock().unwrap().insert(key, err.to_string());
+                return;
+            }
+        };
+
+        let stdout = action::read_all(child.stdout.take().expect("stdout is piped"));
+        let stderr = action::read_all(child.stderr.take().expect("stderr is piped"));
+        let state = Arc::new(Mutex::new(RunState::default()));
+
+        let cache = self.cache.clone();
+        let run = Run {
+            key: key.clone(),
+            pid: child.id(),
+            state: state.clone(),
+        };
+        thread::spawn(move || {
+            let mut text = Vec::new();
+            for reader in [stdout, stderr] {
+                text.extend(reader.join().unwrap_or_default());
+            }
+            loop {
+                let mut state = state.lock().unwrap();
+                if !matches!(child.try_wait(), Ok(None)) {
+                    state.exited = true;
+                    if !state.cancelled {
+                        let text = String::from_utf8_lossy(&text).into_owned();
+                        cache.lock().unwrap().insert(key, text);
+                        crate::request_redraw();
+                    }
+                    return;
+                }
+                drop(state);
+                thread::sleep(Duration::from_millis(10));
+            }
+        });
+        self.running = Some(run);
+    }
+
+    fn cancel(&mut self) {
+        if let Some(run) = self.running.take() {
+            let mut state = run.state.lock().unwrap();
+            state.cancelled = true;
+            if !state.exited {
+                let _ = action::kill_group(run.pid);
+            }
+        }
+    }
+
+    /// Draws the preview for `key`, if there is one, into the area starting at `(col, ln)`.
+    pub fn draw(
+        &self,
+        terminal: &mut RawTerminal<Stdout>,
+        key: Option<&Key>,
+        (col, ln): (usize, usize),
+        (width, height): (usize, usize),
+        theme: &Theme,
+    ) -> io::Result<()> {
+        let Some(key) = key else {
+            return Ok(());
+        };
+        let cache = self.cache.lock().unwrap();
+        let Some(text) = cache.get(key) else {
+            terminal.move_cursor(col, ln)?;
+            terminal.print(&theme.placeholder)?;
+            terminal.print("loading…")?;
+            return terminal.print(termion::style::Reset);
+        };
+
+        for (i, line) in text.lines().take(height).enumerate() {
+            terminal.move_cursor(col, ln + i)?;
+            terminal.print(output::truncate(&line.replace('\t', "    "), width))?;
+            terminal.print(termion::style::Reset)?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Preview {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
@@ -1,19 +1,24 @@
-use std::{
-    io::{self, stderr, stdout, Stdout, Write},
-    process::Command,
-    thread,
-    time::Duration,
-};
+use std::io::{self, Stdout, Write};
 
-use termion::{event::Key, raw::RawTerminal};
+use termion::{
+    event::{Key, MouseButton, MouseEvent},
+    raw::RawTerminal,
+};
 
 use crate::{
-    config::{Action, Config},
-    output::Output,
+    config::{Action, Confirm, Stream},
+    keys,
+    output::{self, Output},
     style::Styled,
+    theme::Theme,
 };
 
-use super::{message::MessageApp, App, Instruction};
+use super::{
+    action::{self, Vars},
+    complete::Completer,
+    validate::Validator,
+    App, Instruction, Snapshot,
+};
 
 pub struct PromptApp {
     pub(super) input: String,
@@ -21,125 +26,226 @@ pub struct PromptApp {
 
     pub(super) placeholder: Styled,
     pub(super) icon: Styled,
+    pub(super) theme: Theme,
     pub(super) action: Action,
     pub(super) history: Option<Vec<(usize, String)>>,
+    /// How many of the newest history entries are scrolled past.
+    pub(super) history_scroll: usize,
+    pub(super) completer: Option<Completer>,
+    pub(super) completion: Option<Completion>,
+    pub(super) confirm: Option<Confirm>,
+    pub(super) validator: Option<Validator>,
+    /// Masks the input, keeps it out of history and passes it to commands
+    /// through stdin rather than `{input}`.
+    pub(super) secret: bool,
+    /// Why the input was rejected, shown until it is edited.
+    pub(super) error: Option<String>,
 
     pub(super) width: usize,
     pub(super) height: usize,
 }
 
-impl App for PromptApp {
-    fn draw(&self, terminal: &mut RawTerminal<Stdout>) -> io::Result<()> {
-        terminal.clear()?;
+/// Open completion candidates, replacing the input from `start` to the
+/// cursor with the selected one.
+pub struct Completion {
+    start: usize,
+    candidates: Vec<String>,
+    selected: usize,
+}
 
-        let cursor_pos = if self.history.is_some() {
+impl PromptApp {
+    /// The column the input starts at.
+    fn input_offset(&self) -> usize {
+        if self.history.is_some() {
             let msg_width = self.placeholder.len() + 2;
-            let prompt_offset = self.width / 2 - msg_width / 2;
-            terminal.print(" ".repeat(prompt_offset))?;
+            self.width / 2 - msg_width / 2 + 3
+        } else {
+            3
+        }
+    }
 
-            self.cursor_index + prompt_offset + 3
+    /// Cycles through completions of the word before the cursor, opening
+    /// them first if needed. A single candidate is inserted right away.
+    fn complete(&mut self, step: isize) {
+        if let Some(completion) = &mut self.completion {
+            let len = completion.candidates.len() as isize;
+            completion.selected = (completion.selected as isize + step).rem_euclid(len) as usize;
         } else {
-            self.cursor_index + 3
+            let Some(completer) = &self.completer else {
+                return;
+            };
+            let cursor = self.byte_index(self.cursor_index);
+            let start = self.input[..cursor].rfind(' ').map_or(0, |i| i + 1);
+            let word = &self.input[start..cursor];
+            let candidates = completer.complete(&self.input, self.cursor_index, word);
+            if candidates.is_empty() {
+                return;
+            }
+            self.completion = Some(Completion {
+                start,
+                selected: if step < 0 { candidates.len() - 1 } else { 0 },
+                candidates,
+            });
+        }
+
+        let Some(completion) = &self.completion else {
+            return;
         };
+        let candidate = completion.candidates[completion.selected].clone();
+        let start = completion.start;
+        if completion.candidates.len() == 1 {
+            self.completion = None;
+        }
+        let cursor = self.byte_index(self.cursor_index);
+        self.input.replace_range(start..cursor, &candidate);
+        self.cursor_index = self.input[..start].chars().count() + candidate.chars().count();
+    }
+
+    /// The byte offset of the character at `cursor`.
+    fn byte_index(&self, cursor: usize) -> usize {
+        self.input
+            .char_indices()
+            .nth(cursor)
+            .map_or(self.input.len(), |(i, _)| i)
+    }
+
+    fn vars(&self) -> Vars {
+        if self.secret {
+            Vars::default().with_secret("input", &self.input)
+        } else {
+            Vars::default().with("input", &self.input)
+        }
+    }
+
+    fn accept(&mut self) -> Instruction {
+        let vars = self.vars();
+        if let Some(validator) = &self.validator {
+            if let Err(err) = validator.validate(&self.input, &vars) {
+                self.error = Some(err);
+                return Instruction::None;
+            }
+        }
+
+        match &self.action {
+            Action::Command(command)
+                if !command.hold_output && self.history.is_some() && !self.secret =>
+            {
+                let out = action::command(command, &vars, Stream::Capture).output();
+                if let Some(vec) = &mut self.history {
+                    let str = match out {
+                        Ok(out) => action::captured(&out),
+                        Err(err) => err.to_string(),
+                    }
+                    .replace("\n", "\n\r ");
+                    let size = str.matches("\n").count();
+
+                    vec.push((size, str));
+                    self.history_scroll = 0;
+                }
+
+                self.input = String::new();
+                self.cursor_index = 0;
+                Instruction::None
+            }
+            action => {
+                let question = String::from("continue?");
+                action::run_confirmed(action, self.confirm.as_ref(), question, &vars, &self.theme)
+            }
+        }
+    }
+}
+
+impl App for PromptApp {
+    fn draw(&self, terminal: &mut RawTerminal<Stdout>) -> io::Result<()> {
+        terminal.clear()?;
 
+        let offset = self.input_offset();
+        terminal.print(" ".repeat(offset - 3))?;
         terminal.print(&self.icon)?;
         terminal.print(" ")?;
         if self.input.is_empty() {
             terminal.print(&self.placeholder)?;
         } else {
-            terminal.print(&self.input)?;
+            terminal.print(&self.theme.prompt)?;
+            if self.secret {
+                terminal.print("•".repeat(self.input.chars().count()))?;
+            } else {
+                terminal.print(&self.input)?;
+            }
+            terminal.print(termion::style::Reset)?;
+        }
+
+        if let Some(error) = &self.error {
+            let used = offset + self.input.chars().count() + 2;
+            let error = output::truncate(error, self.width.saturating_sub(used));
+            terminal.move_cursor(self.width + 1 - error.chars().count(), 1)?;
+            terminal.print(Styled {
+                text: error,
+                style: self.theme.error.clone(),
+            })?;
         }
 
         if let Some(vec) = &self.history {
             terminal.move_cursor(1, 2)?;
-            terminal.print("\x1b[38;5;235m")?;
-            terminal.print("─".repeat(self.width))?;
-            terminal.print("\x1b[0m")?;
+            terminal.divider(self.width, &self.theme.divider)?;
 
             let mut ln = 0;
-            for (size, entry) in vec.iter().rev().take(self.height - 2) {
+            for (size, entry) in vec
+                .iter()
+                .rev()
+                .skip(self.history_scroll)
+                .take(self.height - 2)
+            {
                 terminal.move_cursor(1, ln + 3)?;
                 terminal.print(" ")?;
-                terminal.print(&entry)?;
+                terminal.print(entry)?;
 
                 ln += size;
             }
         }
 
-        terminal.move_cursor(cursor_pos, 1)?;
+        if let Some(completion) = &self.completion {
+            let col = offset + self.input[..completion.start].chars().count();
+            let visible = self.height.saturating_sub(1);
+            let skip = (completion.selected + 1).saturating_sub(visible);
+            for (i, candidate) in completion
+                .candidates
+                .iter()
+                .enumerate()
+                .skip(skip)
+                .take(visible)
+            {
+                let style = if i == completion.selected {
+                    &self.theme.highlight
+                } else {
+                    &self.theme.placeholder
+                };
+                terminal.move_cursor(col, i - skip + 2)?;
+                terminal.print(termion::clear::UntilNewline)?;
+                terminal.print(Styled {
+                    text: output::truncate(candidate, self.width.saturating_sub(col)),
+                    style: style.clone(),
+                })?;
+            }
+        }
+
+        terminal.move_cursor(offset + self.cursor_index, 1)?;
         terminal.flush()
     }
 
     fn handle_input(&mut self, key: Key) -> Instruction {
+        self.completion = None;
+        self.error = None;
         match key {
-            Key::Char('\n') => match &self.action {
-                Action::Exec(name) => {
-                    let output = Command::new("hyprctl")
-                        .args(["dispatch", "exec"])
-                        .arg(name.replace("{input}", &self.input))
-                        .output();
-                    if let Err(err) = output {
-                        Instruction::SetApp(Box::new(MessageApp(format!("{err}").into())))
-                    } else {
-                        Instruction::Quit
-                    }
-                }
-                Action::Command {
-                    name,
-                    args,
-                    hold_output,
-                    output_size,
-                } => {
-                    let mut command = Command::new(name);
-                    command.args(args.into_iter().map(|s| s.replace("{input}", &self.input)));
-                    if *hold_output {
-                        if let Some((w, h)) = output_size {
-                            Command::new("hyprctl")
-                                    .args([
-                                        "--batch",
-                                        &format!("dispatch resizeactive exact {w} {h}; dispatch centerwindow"),
-                                    ])
-                                    .output()
-                                    .expect("failed to resize window");
-                            thread::sleep(Duration::from_millis(100));
-                        }
-
-                        command.stdout(stdout()).stderr(stderr());
-                        Instruction::HoldOutput(command)
-                    } else {
-                        let out = command.output();
-                        if let Some(vec) = &mut self.history {
-                            let str = match out {
-                                Ok(out) => String::from_utf8_lossy(&out.stdout).to_string(),
-                                Err(err) => err.to_string(),
-                            }
-                            .replace("\n", "\n\r ");
-                            let size = str.matches("\n").count();
-
-                            vec.push((size, str));
-
-                            self.input = String::new();
-                            self.cursor_index = 0;
-                            Instruction::None
-                        } else {
-                            Instruction::Quit
-                        }
-                    }
-                }
-                Action::OpenMenu(name) => Instruction::SetApp(super::from_config(
-                    Config::get_menu(name.replace("{input}", &self.input)),
-                )),
-            },
-
             Key::Backspace => {
                 if self.cursor_index > 0 {
-                    self.input.remove(self.cursor_index - 1);
+                    self.input.remove(self.byte_index(self.cursor_index - 1));
                     self.cursor_index -= 1;
                 }
                 Instruction::None
             }
             Key::Char(ch) => {
-                self.input.insert(self.cursor_index, ch);
+                self.input.insert(self.byte_index(self.cursor_index), ch);
                 self.cursor_index += 1;
                 Instruction::None
             }
@@ -151,7 +257,7 @@ impl App for PromptApp {
                 Instruction::None
             }
             Key::Right => {
-                if self.cursor_index < self.input.len() {
+                if self.cursor_index < self.input.chars().count() {
                     self.cursor_index += 1;
                 }
                 Instruction::None
@@ -161,5 +267,61 @@ impl App for PromptApp {
         }
     }
 
+    fn handle_command(&mut self, command: keys::Command) -> Instruction {
+        match command {
+            keys::Command::Accept => self.accept(),
+            keys::Command::AcceptAlt => match self.accept() {
+                Instruction::Quit => Instruction::None,
+                instruction => instruction,
+            },
+            keys::Command::ClearFilter => {
+                self.input.clear();
+                self.cursor_index = 0;
+                self.completion = None;
+                self.error = None;
+                Instruction::None
+            }
+            keys::Command::SelectNext => {
+                self.complete(1);
+                Instruction::None
+            }
+            keys::Command::SelectPrev => {
+                self.complete(-1);
+                Instruction::None
+            }
+            _ => Instruction::None,
+        }
+    }
+
+    fn handle_mouse(&mut self, event: MouseEvent) -> Instruction {
+        let Some(vec) = &self.history else {
+            return Instruction::None;
+        };
+
+        match event {
+            MouseEvent::Press(MouseButton::WheelUp, _, _) => {
+                self.history_scroll = self.history_scroll.saturating_sub(1);
+            }
+            MouseEvent::Press(MouseButton::WheelDown, _, _) => {
+                self.history_scroll = (self.history_scroll + 1).min(vec.len().saturating_sub(1));
+            }
+            _ => (),
+        }
+        Instruction::None
+    }
+
     fn handle_resize(&mut self, _: usize, _: usize) {}
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            input: self.input.clone(),
+            cursor: self.cursor_index,
+            ..Default::default()
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.cursor_index = snapshot.cursor.min(snapshot.input.chars().count());
+        self.input = snapshot.input;
+    }
 }
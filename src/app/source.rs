@@ -0,0 +1,52 @@
+use std::{env, mem};
+
+use serde::Deserialize;
+
+use crate::config::{Entry, Error, ListConfig};
+
+use super::{desktop, windows};
+
+/// Where a list gets entries besides the ones in its config.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EntrySource {
+    /// The applications in the XDG data dirs.
+    Desktop,
+    /// The open windows, which are focused when chosen.
+    Windows,
+}
+
+impl EntrySource {
+    pub fn entries(&self, list: &ListConfig) -> Result<Vec<Entry>, Error> {
+        match self {
+            EntrySource::Desktop => {
+                let terminal = list.terminal.clone().or_else(|| env::var("TERMINAL").ok());
+                Ok(desktop::entries(terminal.as_deref()))
+            }
+            EntrySource::Windows => windows::entries().map_err(Error::Source),
+        }
+    }
+}
+
+/// Adds the entries from the list's source, if it has one. Entries in the
+/// config replace generated ones with the same name, and generated ones
+/// named in `hide` are left out.
+pub fn resolve(list: &mut ListConfig) -> Result<(), Error> {
+    let Some(source) = &list.entries_from else {
+        return Ok(());
+    };
+    let mut manual = mem::take(&mut list.entries);
+    let generated: Vec<_> = source
+        .entries(list)?
+        .into_iter()
+        .filter(|entry| !list.hide.contains(&entry.name))
+        .map(
+            |entry| match manual.iter().position(|own| own.name == entry.name) {
+                Some(i) => manual.remove(i),
+                None => entry,
+            },
+        )
+        .collect();
+    list.entries = manual.into_iter().chain(generated).collect();
+    Ok(())
+}
@@ -0,0 +1,125 @@
+use std::io::{self, Stdout, Write};
+
+use termion::{
+    event::{Key, MouseButton, MouseEvent},
+    raw::RawTerminal,
+};
+
+use crate::{
+    keys::{self, Keymap},
+    output::Output,
+    style::Styled,
+    theme::Theme,
+};
+
+use super::{App, Instruction, Snapshot};
+
+pub struct Tab {
+    pub(super) name: String,
+    pub(super) app: Box<dyn App>,
+    pub(super) keys: Keymap,
+}
+
+/// Several menus in one window, with a tab bar drawn over the divider. Each
+/// tab keeps its own state while hidden.
+pub struct TabsApp {
+    pub(super) tabs: Vec<Tab>,
+    pub(super) current: usize,
+    pub(super) theme: Theme,
+}
+
+impl TabsApp {
+    fn tab(&mut self) -> &mut Tab {
+        &mut self.tabs[self.current]
+    }
+
+    /// The columns taken by each tab's label, from left to right.
+    fn labels(&self) -> Vec<(usize, usize)> {
+        let mut col = 2;
+        self.tabs
+            .iter()
+            .map(|tab| {
+                let start = col;
+                col += tab.name.chars().count() + 3;
+                (start, start + tab.name.chars().count() + 2)
+            })
+            .collect()
+    }
+}
+
+impl App for TabsApp {
+    fn draw(&self, terminal: &mut RawTerminal<Stdout>) -> io::Result<()> {
+        self.tabs[self.current].app.draw(terminal)?;
+
+        write!(terminal, "{}", termion::cursor::Save)?;
+        for ((col, _), (i, tab)) in self.labels().into_iter().zip(self.tabs.iter().enumerate()) {
+            let style = if i == self.current {
+                &self.theme.header
+            } else {
+                &self.theme.placeholder
+            };
+            terminal.move_cursor(col, 2)?;
+            terminal.print(Styled {
+                text: format!(" {} ", tab.name),
+                style: style.clone(),
+            })?;
+        }
+        write!(terminal, "{}", termion::cursor::Restore)?;
+        terminal.flush()
+    }
+
+    fn handle_input(&mut self, key: Key) -> Instruction {
+        self.tab().app.handle_input(key)
+    }
+
+    fn handle_command(&mut self, command: keys::Command) -> Instruction {
+        match command {
+            keys::Command::NextTab => {
+                self.current = (self.current + 1) % self.tabs.len();
+                Instruction::None
+            }
+            keys::Command::PrevTab => {
+                self.current = (self.current + self.tabs.len() - 1) % self.tabs.len();
+                Instruction::None
+            }
+            command => self.tab().app.handle_command(command),
+        }
+    }
+
+    fn handle_mouse(&mut self, event: MouseEvent) -> Instruction {
+        if let MouseEvent::Press(MouseButton::Left, x, 2) = event {
+            let x = x as usize;
+            if let Some(i) = self
+                .labels()
+                .into_iter()
+                .position(|(start, end)| (start..end).contains(&x))
+            {
+                self.current = i;
+            }
+            return Instruction::None;
+        }
+        self.tab().app.handle_mouse(event)
+    }
+
+    fn handle_resize(&mut self, width: usize, height: usize) {
+        for tab in &mut self.tabs {
+            tab.app.handle_resize(width, height);
+        }
+    }
+
+    fn keymap(&self) -> Option<&Keymap> {
+        Some(&self.tabs[self.current].keys)
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            tab: self.current,
+            ..self.tabs[self.current].app.snapshot()
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.current = snapshot.tab.min(self.tabs.len() - 1);
+        self.tab().app.restore(snapshot);
+    }
+}
@@ -0,0 +1,74 @@
+use std::process::{Command, Stdio};
+
+use regex::Regex;
+use serde::Deserialize;
+
+use super::action::{Process, Vars};
+
+/// Checks prompt input before it is accepted.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Validator {
+    /// The input must match the pattern.
+    Regex(Pattern),
+    /// The command, run with `sh -c` and the input on stdin, must succeed.
+    /// Its output is shown as the error otherwise. Values such as `{input}`
+    /// are expanded quoted.
+    Command(String),
+}
+
+#[derive(Deserialize)]
+#[serde(try_from = "String")]
+pub struct Pattern(Regex);
+
+impl TryFrom<String> for Pattern {
+    type Error = regex::Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Regex::new(&value).map(Self)
+    }
+}
+
+impl Validator {
+    /// Returns the error to show if `input` is invalid.
+    pub fn validate(&self, input: &str, vars: &Vars) -> Result<(), String> {
+        match self {
+            Validator::Regex(Pattern(regex)) => {
+                if regex.is_match(input) {
+                    Ok(())
+                } else {
+                    Err(format!("must match {}", regex.as_str()))
+                }
+            }
+            Validator::Command(template) => {
+                let mut command = Command::new("sh");
+                command
+                    .args(["-c", &vars.expand_shell(template)])
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::piped());
+                let output = Process {
+                    stdin: Some(input.to_string()),
+                    ..Process::new(command)
+                }
+                .output()
+                .map_err(|err| err.to_string())?;
+                if output.status.success() {
+                    return Ok(());
+                }
+
+                let message = [output.stderr, output.stdout]
+                    .iter()
+                    .filter_map(|text| {
+                        let text = String::from_utf8_lossy(text);
+                        Some(
+                            text.lines()
+                                .find(|line| !line.trim().is_empty())?
+                                .to_string(),
+                        )
+                    })
+                    .next();
+                Err(message.unwrap_or_else(|| String::from("invalid input")))
+            }
+        }
+    }
+}
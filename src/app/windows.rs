@@ -0,0 +1,192 @@
+use std::process::Command;
+
+use serde::Deserialize;
+
+use crate::config::{Action, CommandAction, Entry};
+
+const ICON: &str = "\u{f2d0}";
+
+/// A window as listed by `hyprctl clients -j`.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Window {
+    pub address: String,
+    #[serde(default)]
+    pub class: String,
+    #[serde(default)]
+    pub title: String,
+    pub workspace: Workspace,
+    #[serde(default = "Window::default_mapped")]
+    pub mapped: bool,
+    #[serde(default)]
+    pub hidden: bool,
+    /// How recently the window was focused, starting at 0 for the focused
+    /// one.
+    #[serde(rename = "focusHistoryID", default)]
+    pub focus_history_id: i64,
+}
+
+#[derive(Deserialize)]
+pub struct Workspace {
+    pub name: String,
+}
+
+impl Window {
+    fn default_mapped() -> bool {
+        true
+    }
+
+    /// The arguments to `hyprctl` that focus the window.
+    pub fn focus_args(&self) -> Vec<String> {
+        vec![
+            String::from("dispatch"),
+            String::from("focuswindow"),
+            format!("address:{}", self.address),
+        ]
+    }
+
+    pub fn entry(&self) -> Entry {
+        let name = if self.title.is_empty() {
+            self.class.clone()
+        } else {
+            self.title.clone()
+        };
+        Entry {
+            name,
+            icon: String::from(ICON),
+            keywords: Some(self.class.clone()).filter(|class| !class.is_empty()),
+            description: Some(format!("{} on {}", self.class, self.workspace.name)),
+            key: None,
+            preview: None,
+            source: None,
+            confirm: None,
+            action: Action::Command(Box::new(CommandAction {
+                name: String::from("hyprctl"),
+                args: self.focus_args(),
+                ..Default::default()
+            })),
+        }
+    }
+}
+
+/// Reads the output of `hyprctl clients -j`, most recently focused first.
+/// The focused window, which is the menu itself, is left out, as are
+/// windows that are not shown.
+pub fn parse(json: &str) -> serde_json::Result<Vec<Window>> {
+    let mut windows: Vec<Window> = serde_json::from_str(json)?;
+    windows.retain(|window| window.mapped && !window.hidden && window.focus_history_id != 0);
+    windows.sort_by_key(|window| window.focus_history_id);
+    Ok(windows)
+}
+
+/// Entries that focus the open windows.
+pub fn entries() -> Result<Vec<Entry>, String> {
+    let output = Command::new("hyprctl")
+        .args(["clients", "-j"])
+        .output()
+        .map_err(|err| format!("failed to run hyprctl: {err}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!(
+            "hyprctl failed ({}): {}",
+            output.status,
+            stderr.trim()
+        ));
+    }
+    let windows = parse(&String::from_utf8_lossy(&output.stdout))
+        .map_err(|err| format!("invalid hyprctl output: {err}"))?;
+    Ok(windows.iter().map(Window::entry).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Trimmed `hyprctl clients -j` output.
+    const CLIENTS: &str = r#"[
+        {
+            "address": "0x55d1a0",
+            "mapped": true,
+            "hidden": false,
+            "at": [0, 0],
+            "size": [480, 320],
+            "workspace": { "id": 1, "name": "1" },
+            "floating": true,
+            "class": "kitty",
+            "title": "toolbelt",
+            "focusHistoryID": 0
+        },
+        {
+            "address": "0x55d1b0",
+            "mapped": true,
+            "hidden": false,
+            "workspace": { "id": 2, "name": "2" },
+            "class": "firefox",
+            "title": "GitHub - Mozilla Firefox",
+            "focusHistoryID": 2
+        },
+        {
+            "address": "0x55d1c0",
+            "mapped": true,
+            "hidden": false,
+            "workspace": { "id": -98, "name": "special:scratch" },
+            "class": "Slack",
+            "title": "",
+            "focusHistoryID": 1
+        },
+        {
+            "address": "0x55d1d0",
+            "mapped": false,
+            "hidden": false,
+            "workspace": { "id": -1, "name": "" },
+            "class": "ghost",
+            "title": "ghost",
+            "focusHistoryID": 3
+        }
+    ]"#;
+
+    #[test]
+    fn lists_recently_focused_first() {
+        let windows = parse(CLIENTS).unwrap();
+        let addresses: Vec<_> = windows.iter().map(|w| w.address.as_str()).collect();
+        assert_eq!(addresses, ["0x55d1c0", "0x55d1b0"]);
+    }
+
+    #[test]
+    fn leaves_out_focused_window() {
+        let windows = parse(CLIENTS).unwrap();
+        assert!(windows.iter().all(|window| window.title != "toolbelt"));
+    }
+
+    #[test]
+    fn focuses_by_address() {
+        let windows = parse(CLIENTS).unwrap();
+        assert_eq!(
+            windows[1].focus_args(),
+            ["dispatch", "focuswindow", "address:0x55d1b0"]
+        );
+
+        let entry = windows[1].entry();
+        assert_eq!(entry.name, "GitHub - Mozilla Firefox");
+        assert_eq!(entry.description.as_deref(), Some("firefox on 2"));
+        match entry.action {
+            Action::Command(command) => {
+                assert_eq!(command.name, "hyprctl");
+                assert_eq!(command.args, windows[1].focus_args());
+            }
+            _ => panic!("windows are focused with a command"),
+        }
+    }
+
+    #[test]
+    fn names_untitled_windows_by_class() {
+        let windows = parse(CLIENTS).unwrap();
+        assert_eq!(windows[0].entry().name, "Slack");
+    }
+
+    #[test]
+    fn rejects_malformed_output() {
+        assert!(parse("hyprctl: no such socket").is_err());
+        assert!(parse(r#"[{ "class": "kitty" }]"#).is_err());
+    }
+}
@@ -1,8 +1,16 @@
-use std::{env, fs, path::PathBuf};
+use std::{collections::HashMap, env, fmt::Display, fs, io, path::PathBuf};
 
 use serde::Deserialize;
 
-use crate::style::{Style, Styled};
+use crate::{
+    app::{
+        complete::Completer, files::Glob, preview::Position, source::EntrySource,
+        validate::Validator,
+    },
+    keys::{Chord, Keymap},
+    style::{Style, Styled},
+    theme::Theme,
+};
 
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -10,38 +18,254 @@ pub struct Config {
     pub prompt: Text,
     pub icon: Text,
     pub window_size: Option<(usize, usize)>,
+    /// Bindings from the menu, merged over the global ones by `Config::load`.
+    #[serde(default)]
+    pub keys: Keymap,
+
+    /// The name the config was loaded by, if it was read from a file.
+    #[serde(skip)]
+    pub name: Option<String>,
+    /// Resolved from the optional `theme` name; see `Config::load`.
+    #[serde(skip)]
+    pub theme: Theme,
+    /// Files this config was read from, used to decide when to reload it.
+    #[serde(skip)]
+    pub sources: Vec<PathBuf>,
 
     #[serde(flatten)]
     pub menu: MenuConfig,
 }
 
 impl Config {
-    pub fn get_menu(name: String) -> Self {
-        let mut menu_path =
-            PathBuf::from(env::var_os("HOME").expect("failed to get home directory"));
-        menu_path.push(".config/toolbelt/");
-        menu_path.push(name + ".json");
-
-        let file = fs::read_to_string(menu_path).expect("failed to open config file");
-        serde_json::from_str(&file).expect("failed to parse config")
+    pub fn load(name: &str) -> Result<Self, Error> {
+        let path = Self::menu_path(name);
+        let file = fs::read_to_string(&path).map_err(Error::Io)?;
+        let mut config = Self::parse(&file)?;
+        config.sources.insert(0, path);
+        config.name = Some(name.to_string());
+        Ok(config)
+    }
+
+    /// Parses a menu definition, resolving its theme and layering its keys.
+    pub fn parse(text: &str) -> Result<Self, Error> {
+        let value: serde_json::Value = serde_json::from_str(text).map_err(Error::Parse)?;
+
+        let theme_name = value.get("theme").and_then(|name| name.as_str());
+        let theme = Theme::load(theme_name)?;
+        let mut sources = vec![Theme::path(theme_name), Keymap::path()];
+
+        let mut config: Self = theme
+            .scope(|| serde_json::from_value(value))
+            .map_err(Error::Parse)?;
+        config.menu.check_secrets()?;
+        if matches!(&config.menu, MenuConfig::Tabs { tabs } if tabs.is_empty()) {
+            return Err(Error::NoTabs);
+        }
+        let mut keys = Keymap::builtin();
+        if matches!(&config.menu, MenuConfig::List(list) if list.multi) {
+            keys.extend(Keymap::multi());
+        }
+        keys.extend(Keymap::load()?);
+        keys.extend(config.keys);
+
+        if let MenuConfig::Combine { menus } | MenuConfig::Tabs { tabs: menus } = &config.menu {
+            sources.extend(menus.iter().map(|name| Self::menu_path(name)));
+        }
+
+        config.keys = keys;
+        config.theme = theme;
+        config.sources = sources;
+        Ok(config)
+    }
+
+    pub fn dir() -> PathBuf {
+        let mut dir = PathBuf::from(env::var_os("HOME").expect("failed to get home directory"));
+        dir.push(".config/toolbelt/");
+        dir
+    }
+
+    pub fn menu_path(name: &str) -> PathBuf {
+        Self::dir().join(format!("{name}.json"))
+    }
+}
+
+pub enum Error {
+    Io(io::Error),
+    Parse(serde_json::Error),
+    /// An error in a menu included by another.
+    Menu(String, Box<Error>),
+    /// A menu that can't be included in a combine menu.
+    NotCombinable(String),
+    /// A tabs menu that includes itself, directly or through other tabs.
+    Cycle(String),
+    /// A tabs menu without any tabs.
+    NoTabs,
+    /// Secret input with an action that can't receive it.
+    Secret,
+    /// A list's `entriesFrom` source failed.
+    Source(String),
+}
+
+impl Error {
+    /// Marks the error as coming from the included menu `name`.
+    pub fn in_menu(self, name: &str) -> Self {
+        Error::Menu(name.to_string(), Box::new(self))
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "failed to open config file: {err}"),
+            Error::Parse(err) => write!(f, "failed to parse config: {err}"),
+            Error::Menu(name, err) => write!(f, "in menu {name}: {err}"),
+            Error::NotCombinable(name) => write!(
+                f,
+                "cannot combine {name}: only list menus and prompts without secret or validate can be"
+            ),
+            Error::Cycle(name) => write!(f, "menu {name} includes itself as a tab"),
+            Error::NoTabs => write!(f, "tabs menus need at least one tab"),
+            Error::Source(err) => write!(f, "failed to generate entries: {err}"),
+            Error::Secret => write!(
+                f,
+                "secret input needs a command action, which gets it on stdin, or a print action"
+            ),
+        }
     }
 }
 
 #[derive(Deserialize)]
 #[serde(tag = "type", rename_all = "camelCase")]
 pub enum MenuConfig {
-    #[serde(rename_all = "camelCase")]
-    List {
-        entries: Vec<Entry>,
-        #[serde(default)]
-        selected_style: Style,
+    List(Box<ListConfig>),
+    /// Searches the entries of several other menus at once.
+    Combine {
+        menus: Vec<String>,
+    },
+    /// Shows several other menus in one window, switched between with
+    /// `next-tab` and `prev-tab`.
+    Tabs {
+        tabs: Vec<String>,
     },
     Prompt {
         #[serde(flatten)]
         action: Action,
         #[serde(default)]
         history: bool,
+        /// Completes the word before the cursor on `select-next`.
+        completer: Option<Completer>,
+        confirm: Option<Confirm>,
+        validate: Option<Validator>,
+        #[serde(default)]
+        secret: bool,
+    },
+    /// Browses the files under a directory.
+    Files(Box<FilesConfig>),
+    /// Several named inputs, available to the action as `{field.<name>}`.
+    Form {
+        fields: Vec<FormField>,
+        #[serde(flatten)]
+        action: Action,
+        confirm: Option<Confirm>,
+    },
+}
+
+impl MenuConfig {
+    /// Secret values only reach commands through stdin, so other actions
+    /// would silently lose them.
+    fn check_secrets(&self) -> Result<(), Error> {
+        let (secret, action) = match self {
+            MenuConfig::Prompt { secret, action, .. } => (*secret, action),
+            MenuConfig::Form { fields, action, .. } => (
+                fields
+                    .iter()
+                    .any(|field| matches!(field.kind, FieldKind::Secret)),
+                action,
+            ),
+            _ => return Ok(()),
+        };
+        match action {
+            _ if !secret => Ok(()),
+            Action::Command(_) | Action::Print(_) => Ok(()),
+            _ => Err(Error::Secret),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct FilesConfig {
+    /// The directory to start in, which may start with `~`.
+    pub root: String,
+    /// Globs that file names must match, if any are given. Directories are
+    /// always listed.
+    pub include: Vec<Glob>,
+    /// Globs of file and directory names to leave out.
+    pub exclude: Vec<Glob>,
+    /// Lists hidden files until `toggle-hidden` is used.
+    pub hidden: bool,
+    /// Run on the chosen file with `{path}`, `{name}` and `{dir}`. Opens it
+    /// with `xdg-open` by default.
+    pub open: Option<Action>,
+    /// Asks before running `open`.
+    pub confirm: Option<Confirm>,
+}
+
+impl Default for FilesConfig {
+    fn default() -> Self {
+        Self {
+            root: String::from("~"),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            hidden: false,
+            open: None,
+            confirm: None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct FormField {
+    pub name: String,
+    /// Shown before the input, defaulting to the name.
+    pub label: Option<String>,
+    #[serde(flatten)]
+    pub kind: FieldKind,
+    pub validate: Option<Validator>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum FieldKind {
+    Text {
+        #[serde(default)]
+        default: String,
+    },
+    /// Masked text, passed to commands through stdin like secret prompts.
+    Secret,
+    Choice {
+        options: Vec<String>,
+        default: Option<String>,
     },
+    /// Passed to the action as `on` or `off`.
+    Toggle {
+        #[serde(default)]
+        default: bool,
+        #[serde(default = "FieldKind::default_on")]
+        on: String,
+        #[serde(default = "FieldKind::default_off")]
+        off: String,
+    },
+}
+
+impl FieldKind {
+    fn default_on() -> String {
+        String::from("true")
+    }
+
+    fn default_off() -> String {
+        String::from("false")
+    }
 }
 
 #[derive(Deserialize)]
@@ -51,6 +275,19 @@ pub enum Text {
     Styled(Styled),
 }
 
+impl Text {
+    /// Converts to styled text, using `style` if none was given.
+    pub fn or_style(self, style: &Style) -> Styled {
+        match self {
+            Text::Styled(s) => s,
+            Text::Unstyled(text) => Styled {
+                text,
+                style: style.clone(),
+            },
+        }
+    }
+}
+
 impl From<Text> for Styled {
     fn from(value: Text) -> Self {
         match value {
@@ -60,27 +297,196 @@ impl From<Text> for Styled {
     }
 }
 
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ListConfig {
+    pub entries: Vec<Entry>,
+    /// Generates more entries: `"desktop"` for installed applications or
+    /// `"windows"` for open windows.
+    pub entries_from: Option<EntrySource>,
+    /// Names of generated entries to leave out.
+    pub hide: Vec<String>,
+    /// Runs desktop apps with `Terminal=true` as `<terminal> -e <command>`.
+    /// Defaults to `$TERMINAL`; without either, such apps are left out.
+    pub terminal: Option<String>,
+    pub groups: Vec<Group>,
+    pub search_groups: bool,
+    pub hotkey_mode: bool,
+    pub multi: bool,
+    pub batch: Option<Action>,
+    pub batch_confirm: Option<Confirm>,
+    pub selected_style: Option<Style>,
+    pub highlight_style: Option<Style>,
+    /// Command run with `sh -c` to preview the selected entry, with `{name}`
+    /// and `{input}` quoted as single arguments.
+    pub preview: Option<String>,
+    pub preview_position: Position,
+    /// Shown after the results while filtering, so the filter can be run as
+    /// input when nothing matches.
+    pub fallback: Option<Fallback>,
+    /// Rows shown after the results while filtering, including `fallback`.
+    #[serde(skip)]
+    pub fallbacks: Vec<Fallback>,
+}
+
+/// A row that runs its action with the filter as `{input}`.
+#[derive(Deserialize)]
+pub struct Fallback {
+    #[serde(default = "Fallback::default_name")]
+    pub name: String,
+    #[serde(default)]
+    pub icon: String,
+    pub confirm: Option<Confirm>,
+    #[serde(flatten)]
+    pub action: Action,
+}
+
+impl Fallback {
+    fn default_name() -> String {
+        String::from("run '{input}'")
+    }
+}
+
+#[derive(Deserialize)]
+pub struct Group {
+    pub title: String,
+    pub entries: Vec<Entry>,
+}
+
 #[derive(Deserialize)]
 pub struct Entry {
     pub name: String,
     pub icon: String,
     pub keywords: Option<String>,
+    pub description: Option<String>,
+    pub key: Option<Chord>,
+    pub preview: Option<String>,
+    /// The menu the entry was taken from in combined menus.
+    #[serde(skip)]
+    pub source: Option<String>,
+    pub confirm: Option<Confirm>,
 
     #[serde(flatten)]
     pub action: Action,
 }
 
-#[derive(Deserialize)]
+/// Asks before running an action: `true`, a question, or
+/// `{ "message": ..., "default": true }` to preselect "yes".
+///
+/// Set where the action to run is chosen, rather than on `Action`: on
+/// entries, fallback rows, prompts and forms, as `batchConfirm` for a list's
+/// `batch`, and for a files menu's `open`. The question replaces the menu
+/// until it is answered, so actions run by another one, such as sequence
+/// steps and follow-ups, can't stop to ask.
+#[derive(Deserialize, Clone)]
+#[serde(untagged)]
+pub enum Confirm {
+    Enabled(bool),
+    Message(String),
+    Full {
+        message: Option<String>,
+        #[serde(default)]
+        default: bool,
+    },
+}
+
+impl Confirm {
+    /// The question to ask, defaulting to `fallback`, and whether "yes" is
+    /// preselected. `None` if confirmation is off.
+    pub fn question(&self, fallback: String) -> Option<(String, bool)> {
+        match self {
+            Confirm::Enabled(false) => None,
+            Confirm::Enabled(true) => Some((fallback, false)),
+            Confirm::Message(message) => Some((message.clone(), false)),
+            Confirm::Full { message, default } => {
+                Some((message.clone().unwrap_or(fallback), *default))
+            }
+        }
+    }
+}
+
+#[derive(Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub enum Action {
     Exec(String),
-    #[serde(rename_all = "camelCase")]
-    Command {
-        name: String,
-        args: Vec<String>,
-        #[serde(default)]
-        hold_output: bool,
-        output_size: Option<(usize, usize)>,
-    },
+    Command(Box<CommandAction>),
     OpenMenu(String),
+    /// Prints the text to stdout after the menu closes.
+    Print(String),
+    /// Shows the text until a key is pressed.
+    Message(String),
+    /// Opens the menu the command prints as JSON.
+    OpenMenuFrom(Box<CommandAction>),
+    Sequence(Sequence),
+}
+
+impl Action {
+    /// Whether the action can run alongside others, which it can't if it
+    /// shows something or opens another menu.
+    pub fn batchable(&self) -> bool {
+        match self {
+            Action::Exec(_) | Action::Print(_) => true,
+            Action::Command(command) => {
+                !command.hold_output
+                    && [&command.on_success, &command.on_failure]
+                        .into_iter()
+                        .flatten()
+                        .all(Action::batchable)
+            }
+            Action::Sequence(sequence) => sequence.actions.iter().all(Action::batchable),
+            Action::OpenMenu(_) | Action::Message(_) | Action::OpenMenuFrom(_) => false,
+        }
+    }
+}
+
+/// Actions run in order. Actions that leave the menu, such as opening another
+/// one or holding output, end the sequence.
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Sequence {
+    pub actions: Vec<Action>,
+    /// Stops at the first action that fails.
+    #[serde(default)]
+    pub stop_on_error: bool,
+}
+
+#[derive(Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandAction {
+    pub name: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub hold_output: bool,
+    pub output_size: Option<(usize, usize)>,
+    /// A template written to the command's stdin.
+    pub stdin: Option<String>,
+    /// Defaults to `inherit` when holding output, `discard` otherwise.
+    pub stdout: Option<Stream>,
+    pub stderr: Option<Stream>,
+    /// The working directory, which may start with `~`.
+    pub cwd: Option<String>,
+    /// Variables set for the command, with their values expanded.
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Starts the command with only the variables in `env`.
+    #[serde(default)]
+    pub clear_env: bool,
+    /// Seconds after which the command is killed.
+    pub timeout: Option<f64>,
+    /// Run after the command exits, with its exit code as `{code}`. Not used
+    /// when holding output.
+    pub on_success: Option<Action>,
+    pub on_failure: Option<Action>,
+}
+
+/// Where a command's output goes.
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Stream {
+    /// Written to the terminal.
+    Inherit,
+    /// Printed after the menu closes, or added to a prompt's history.
+    Capture,
+    Discard,
 }
@@ -0,0 +1,276 @@
+use std::{collections::HashMap, fmt::Display, fs, io, path::PathBuf, str::FromStr};
+
+use serde::Deserialize;
+use termion::event::Key;
+
+use crate::config::{Config, Error};
+
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Command {
+    SelectNext,
+    SelectPrev,
+    /// Runs the selected action.
+    Accept,
+    /// Runs the selected action, keeping the menu open.
+    AcceptAlt,
+    /// Returns to the previous menu, or quits from the first one.
+    Back,
+    Quit,
+    ClearFilter,
+    /// Marks or unmarks the selected entry in multi-select menus.
+    ToggleMark,
+    PageDown,
+    PageUp,
+    TogglePreview,
+    NextTab,
+    PrevTab,
+    /// Shows or hides hidden files in file menus.
+    ToggleHidden,
+}
+
+/// Key bindings for a menu, layered as the builtin ones, then the global
+/// `keys.json`, then the menu's own `keys`; see `Config::load`.
+#[derive(Clone, Default, Deserialize)]
+#[serde(transparent)]
+pub struct Keymap(HashMap<Chord, Command>);
+
+impl Keymap {
+    pub fn builtin() -> Self {
+        Self(HashMap::from([
+            (Chord(Key::Esc), Command::Back),
+            (Chord(Key::Ctrl('c')), Command::Quit),
+            (Chord(Key::Char('\n')), Command::Accept),
+            (Chord(Key::Down), Command::SelectNext),
+            (Chord(Key::Char('\t')), Command::SelectNext),
+            (Chord(Key::Up), Command::SelectPrev),
+            (Chord(Key::BackTab), Command::SelectPrev),
+            (Chord(Key::PageDown), Command::PageDown),
+            (Chord(Key::PageUp), Command::PageUp),
+            (Chord(Key::Ctrl('u')), Command::ClearFilter),
+            (Chord(Key::Alt('p')), Command::TogglePreview),
+            // Terminals send ctrl+tab as a plain tab.
+            (Chord(Key::AltRight), Command::NextTab),
+            (Chord(Key::AltLeft), Command::PrevTab),
+            (Chord(Key::Alt('h')), Command::ToggleHidden),
+        ]))
+    }
+
+    /// Bindings added over the builtin ones in multi-select menus.
+    pub fn multi() -> Self {
+        Self(HashMap::from([
+            (Chord(Key::Char('\t')), Command::ToggleMark),
+            (Chord(Key::Char(' ')), Command::ToggleMark),
+        ]))
+    }
+
+    /// Loads the global `keys.json`.
+    pub fn load() -> Result<Self, Error> {
+        let mut keymap = Self::default();
+        match fs::read_to_string(Self::path()) {
+            Ok(file) => keymap.extend(serde_json::from_str(&file).map_err(Error::Parse)?),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => (),
+            Err(err) => return Err(Error::Io(err)),
+        }
+        Ok(keymap)
+    }
+
+    pub fn path() -> PathBuf {
+        Config::dir().join("keys.json")
+    }
+
+    pub fn extend(&mut self, other: Keymap) {
+        self.0.extend(other.0);
+    }
+
+    /// The bindings for any of `commands`.
+    pub fn only(&self, commands: &[Command]) -> Self {
+        Self(
+            self.0
+                .iter()
+                .filter(|(_, command)| commands.contains(command))
+                .map(|(chord, command)| (*chord, *command))
+                .collect(),
+        )
+    }
+
+    pub fn get(&self, key: Key) -> Option<Command> {
+        self.0.get(&Chord(key)).copied()
+    }
+}
+
+/// A key chord such as `"alt+f"`, `"ctrl+n"`, `"pagedown"` or `"q"`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Chord(pub Key);
+
+impl FromStr for Chord {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid key \"{s}\"");
+        let (modifier, name) = match (s.strip_suffix("++"), s.rsplit_once('+')) {
+            (Some(modifier), _) => (Some(modifier), "+"),
+            (None, Some((modifier, name))) if !name.is_empty() => (Some(modifier), name),
+            _ => (None, s),
+        };
+
+        let mut chars = name.chars();
+        let single = match (chars.next(), chars.next()) {
+            (Some(ch), None) => Some(ch),
+            _ => None,
+        };
+
+        let key = match (modifier, name, single) {
+            (Some("alt"), _, Some(ch)) => Key::Alt(ch),
+            (Some("ctrl"), _, Some(ch)) => Key::Ctrl(ch),
+            (Some("alt"), "left", _) => Key::AltLeft,
+            (Some("alt"), "right", _) => Key::AltRight,
+            (Some("alt"), "up", _) => Key::AltUp,
+            (Some("alt"), "down", _) => Key::AltDown,
+            (Some("ctrl"), "left", _) => Key::CtrlLeft,
+            (Some("ctrl"), "right", _) => Key::CtrlRight,
+            (Some("ctrl"), "up", _) => Key::CtrlUp,
+            (Some("ctrl"), "down", _) => Key::CtrlDown,
+            (Some("ctrl"), "home", _) => Key::CtrlHome,
+            (Some("ctrl"), "end", _) => Key::CtrlEnd,
+            (Some("shift"), "left", _) => Key::ShiftLeft,
+            (Some("shift"), "right", _) => Key::ShiftRight,
+            (Some("shift"), "up", _) => Key::ShiftUp,
+            (Some("shift"), "down", _) => Key::ShiftDown,
+            (Some("shift"), "tab", _) => Key::BackTab,
+            (Some(_), _, _) => return Err(invalid()),
+
+            (None, _, Some(ch)) => Key::Char(ch),
+            (None, "enter", _) => Key::Char('\n'),
+            (None, "tab", _) => Key::Char('\t'),
+            (None, "space", _) => Key::Char(' '),
+            (None, "backspace", _) => Key::Backspace,
+            (None, "delete", _) => Key::Delete,
+            (None, "insert", _) => Key::Insert,
+            (None, "esc", _) => Key::Esc,
+            (None, "left", _) => Key::Left,
+            (None, "right", _) => Key::Right,
+            (None, "up", _) => Key::Up,
+            (None, "down", _) => Key::Down,
+            (None, "home", _) => Key::Home,
+            (None, "end", _) => Key::End,
+            (None, "pageup", _) => Key::PageUp,
+            (None, "pagedown", _) => Key::PageDown,
+            (None, name, _) => match name.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                Some(n @ 1..=12) => Key::F(n),
+                _ => return Err(invalid()),
+            },
+        };
+
+        Ok(Self(key))
+    }
+}
+
+impl TryFrom<String> for Chord {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl Display for Chord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Key::Alt(ch) => write!(f, "alt+{ch}"),
+            Key::Ctrl(ch) => write!(f, "ctrl+{ch}"),
+            Key::Char('\n') => write!(f, "enter"),
+            Key::Char('\t') => write!(f, "tab"),
+            Key::Char(' ') => write!(f, "space"),
+            Key::Char(ch) => write!(f, "{ch}"),
+            Key::F(n) => write!(f, "f{n}"),
+            Key::AltLeft => write!(f, "alt+left"),
+            Key::AltRight => write!(f, "alt+right"),
+            Key::AltUp => write!(f, "alt+up"),
+            Key::AltDown => write!(f, "alt+down"),
+            Key::CtrlLeft => write!(f, "ctrl+left"),
+            Key::CtrlRight => write!(f, "ctrl+right"),
+            Key::CtrlUp => write!(f, "ctrl+up"),
+            Key::CtrlDown => write!(f, "ctrl+down"),
+            Key::CtrlHome => write!(f, "ctrl+home"),
+            Key::CtrlEnd => write!(f, "ctrl+end"),
+            Key::ShiftLeft => write!(f, "shift+left"),
+            Key::ShiftRight => write!(f, "shift+right"),
+            Key::ShiftUp => write!(f, "shift+up"),
+            Key::ShiftDown => write!(f, "shift+down"),
+            Key::BackTab => write!(f, "shift+tab"),
+            Key::Backspace => write!(f, "backspace"),
+            Key::Delete => write!(f, "delete"),
+            Key::Insert => write!(f, "insert"),
+            Key::Esc => write!(f, "esc"),
+            Key::Left => write!(f, "left"),
+            Key::Right => write!(f, "right"),
+            Key::Up => write!(f, "up"),
+            Key::Down => write!(f, "down"),
+            Key::Home => write!(f, "home"),
+            Key::End => write!(f, "end"),
+            Key::PageUp => write!(f, "pageup"),
+            Key::PageDown => write!(f, "pagedown"),
+            _ => write!(f, "?"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> Result<Key, String> {
+        s.parse().map(|Chord(key)| key)
+    }
+
+    #[test]
+    fn plus_is_a_key_of_its_own() {
+        assert_eq!(parse("+"), Ok(Key::Char('+')));
+        assert_eq!(parse("ctrl++"), Ok(Key::Ctrl('+')));
+        assert_eq!(parse("alt++"), Ok(Key::Alt('+')));
+        assert!(parse("++").is_err());
+        assert!(parse("ctrl+").is_err());
+    }
+
+    #[test]
+    fn modifiers_apply_to_chars_and_named_keys() {
+        assert_eq!(parse("alt+f"), Ok(Key::Alt('f')));
+        assert_eq!(parse("ctrl+n"), Ok(Key::Ctrl('n')));
+        assert_eq!(parse("ctrl+home"), Ok(Key::CtrlHome));
+        assert_eq!(parse("shift+tab"), Ok(Key::BackTab));
+        for s in ["shift+a", "ctrl+alt+x", "super+q", "alt+pageup"] {
+            assert!(parse(s).is_err(), "{s}");
+        }
+    }
+
+    #[test]
+    fn function_keys_go_from_f1_to_f12() {
+        assert_eq!(parse("f"), Ok(Key::Char('f')));
+        assert_eq!(parse("f1"), Ok(Key::F(1)));
+        assert_eq!(parse("f12"), Ok(Key::F(12)));
+        for s in ["f0", "f13", "f-1", "F1"] {
+            assert!(parse(s).is_err(), "{s}");
+        }
+    }
+
+    #[test]
+    fn chords_display_as_they_are_written() {
+        for s in [
+            "alt+f",
+            "ctrl++",
+            "+",
+            "enter",
+            "space",
+            "f5",
+            "shift+tab",
+            "pagedown",
+        ] {
+            assert_eq!(
+                s.parse::<Chord>().map(|chord| chord.to_string()),
+                Ok(s.into())
+            );
+        }
+    }
+}
@@ -1,38 +1,142 @@
 use std::{
     env,
-    io::{self, stdin, stdout, Write},
+    io::{self, stdin, stdout, Stdout, Write},
+    path::{Path, PathBuf},
     process,
-    sync::mpsc::{self, Sender},
+    sync::{
+        mpsc::{self, Sender},
+        OnceLock,
+    },
     thread::{self, JoinHandle},
 };
 
+use inotify::{Inotify, WatchMask};
 use output::Output;
 use signal_hook::iterator::Signals;
-use termion::{event::Key, input::TermRead, raw::IntoRawMode, terminal_size};
+use termion::{
+    event::{Key, MouseEvent},
+    input::{MouseTerminal, TermRead},
+    raw::{IntoRawMode, RawTerminal},
+    terminal_size,
+};
 
 use app::{App, Instruction};
 use config::Config;
+use keys::{Command, Keymap};
+use style::Styled;
+use theme::Theme;
 
 mod app;
 mod config;
+mod keys;
+mod matcher;
 mod output;
 mod style;
+mod theme;
+
+/// An open menu. Menus opened from another are stacked on top of it, so
+/// `back` can return to it.
+struct Menu {
+    app: Box<dyn App>,
+    keys: Keymap,
+    /// The name the menu was loaded by and the files it was read from, if it
+    /// can be reloaded.
+    source: Option<(String, Vec<PathBuf>)>,
+}
+
+impl Menu {
+    fn load(name: String) -> Result<Self, config::Error> {
+        let config = Config::load(&name)?;
+        let keys = config.keys.clone();
+        let sources = config.sources.clone();
+        Ok(Self {
+            app: app::from_config(config)?,
+            keys,
+            source: Some((name, sources)),
+        })
+    }
+
+    /// A menu built from a config that was not read from a file.
+    fn generated(config: Config) -> Result<Self, config::Error> {
+        Ok(Self {
+            keys: config.keys.clone(),
+            app: app::from_config(config)?,
+            source: None,
+        })
+    }
+
+    fn depends_on(&self, path: &Path) -> bool {
+        self.source
+            .as_ref()
+            .is_some_and(|(_, sources)| sources.iter().any(|source| source == path))
+    }
+}
 
 enum Event {
     Key(Key),
+    Mouse(MouseEvent),
     Resize(usize, usize),
+    Reload(PathBuf),
+    Redraw,
+}
+
+static EVENTS: OnceLock<Sender<Event>> = OnceLock::new();
+
+/// Asks the main loop to redraw, for apps updated from other threads.
+pub fn request_redraw() {
+    if let Some(sender) = EVENTS.get() {
+        let _ = sender.send(Event::Redraw);
+    }
 }
 
 fn start_resize_thread(sender: Sender<Event>) -> Option<JoinHandle<()>> {
     // SIGWINCH
-    let Ok(mut signals) = Signals::new(&[28]) else {
+    let Ok(mut signals) = Signals::new([28]) else {
         return None;
     };
     Some(thread::spawn(move || loop {
         if signals.pending().count() > 0 {
             let (width, height) = terminal_size().expect("failed to get terminal size.");
-            if let Err(_) = sender.send(Event::Resize(width as usize, height as usize)) {
+            if sender
+                .send(Event::Resize(width as usize, height as usize))
+                .is_err()
+            {
+                break;
+            }
+        }
+    }))
+}
+
+fn start_watch_thread(sender: Sender<Event>) -> Option<JoinHandle<()>> {
+    // Watch the directory rather than the files, since most editors save by
+    // replacing the file.
+    let mut inotify = Inotify::init().ok()?;
+    let mut dirs = Vec::new();
+    for dir in [Config::dir(), Config::dir().join("themes")] {
+        if let Ok(wd) = inotify.watches().add(
+            &dir,
+            WatchMask::CLOSE_WRITE | WatchMask::MOVED_TO | WatchMask::CREATE,
+        ) {
+            dirs.push((wd, dir));
+        }
+    }
+
+    Some(thread::spawn(move || {
+        let mut buffer = [0; 4096];
+        loop {
+            let Ok(events) = inotify.read_events_blocking(&mut buffer) else {
                 break;
+            };
+            for event in events {
+                let Some(name) = event.name else {
+                    continue;
+                };
+                let Some((_, dir)) = dirs.iter().find(|(wd, _)| *wd == event.wd) else {
+                    continue;
+                };
+                if sender.send(Event::Reload(dir.join(name))).is_err() {
+                    return;
+                }
             }
         }
     }))
@@ -43,7 +147,12 @@ fn start_key_thread(sender: Sender<Event>) -> Option<JoinHandle<()>> {
     Some(thread::spawn(move || loop {
         match events.next() {
             Some(Ok(termion::event::Event::Key(key))) => {
-                if let Err(_) = sender.send(Event::Key(key)) {
+                if sender.send(Event::Key(key)).is_err() {
+                    break;
+                }
+            }
+            Some(Ok(termion::event::Event::Mouse(event))) => {
+                if sender.send(Event::Mouse(event)).is_err() {
                     break;
                 }
             }
@@ -55,53 +164,176 @@ fn start_key_thread(sender: Sender<Event>) -> Option<JoinHandle<()>> {
     }))
 }
 
+fn draw_status(terminal: &mut RawTerminal<Stdout>, status: &Styled) -> io::Result<()> {
+    let (_, height) = terminal_size()?;
+
+    write!(terminal, "{}", termion::cursor::Save)?;
+    terminal.move_cursor(1, height as usize)?;
+    terminal.print(termion::clear::CurrentLine)?;
+    terminal.print(status)?;
+    write!(terminal, "{}", termion::cursor::Restore)?;
+    terminal.flush()
+}
+
 fn main() -> io::Result<()> {
-    let menu_name = env::args().skip(1).next().expect("no menu name provided.");
-    let config = Config::get_menu(menu_name);
+    let mut watch = false;
+    let mut menu_name = None;
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--watch" => watch = true,
+            _ => menu_name = Some(arg),
+        }
+    }
+    let menu_name = menu_name.expect("no menu name provided.");
+    let menu = Menu::load(menu_name).unwrap_or_else(|err| panic!("{err}"));
 
-    let mut terminal = stdout().into_raw_mode()?;
+    let mut terminal = MouseTerminal::from(stdout().into_raw_mode()?);
     write!(terminal, "{}", termion::cursor::BlinkingBar)?;
 
-    let mut app: Box<dyn App> = app::from_config(config);
-    app.draw(&mut terminal)?;
-
     let (sender, receiver) = mpsc::channel::<Event>();
+    let _ = EVENTS.set(sender.clone());
+
+    let status_style = Theme::load(None).unwrap_or_default().status_bar;
+    let error_status = |err: config::Error| {
+        Some(Styled {
+            text: err.to_string(),
+            style: status_style.clone(),
+        })
+    };
+    let mut stack = vec![menu];
+    let mut status: Option<Styled> = None;
+    stack[0].app.draw(&mut terminal)?;
+
     start_resize_thread(sender.clone());
+    if watch {
+        start_watch_thread(sender.clone());
+    }
     start_key_thread(sender);
 
     let mut cmd = None;
     for event in &receiver {
-        match event {
-            Event::Key(Key::Esc) => break,
-            Event::Key(key) => match app.handle_input(key) {
-                Instruction::None => (),
-                Instruction::Quit => break,
-                Instruction::SetApp(new_app) => app = new_app,
-                Instruction::HoldOutput(mut command) => {
-                    terminal.clear()?;
-                    terminal.move_cursor(1, 1)?;
-                    terminal.flush()?;
-                    drop(terminal);
-
-                    cmd = Some(command.spawn().expect("failed to spawn"));
-                    break;
+        let menu = stack.last_mut().expect("no menu open");
+        let instruction = match event {
+            Event::Key(key) => match menu.app.keymap().unwrap_or(&menu.keys).get(key) {
+                Some(Command::Quit) => break,
+                Some(Command::Back) => Instruction::Back,
+                Some(command) => menu.app.handle_command(command),
+                None => menu.app.handle_input(key),
+            },
+            Event::Mouse(event) => menu.app.handle_mouse(event),
+            Event::Redraw => Instruction::None,
+
+            Event::Resize(w, h) => {
+                for menu in &mut stack {
+                    menu.app.handle_resize(w, h);
                 }
+                Instruction::None
+            }
+            Event::Reload(path) => {
+                let Some((name, _)) = &menu.source else {
+                    continue;
+                };
+                if !menu.depends_on(&path) {
+                    continue;
+                }
+
+                match Menu::load(name.clone()) {
+                    Ok(reloaded) => {
+                        let snapshot = menu.app.snapshot();
+                        *menu = reloaded;
+                        menu.app.restore(snapshot);
+                        status = None;
+                    }
+                    Err(err) => status = error_status(err),
+                }
+                Instruction::None
+            }
+        };
+
+        match instruction {
+            Instruction::None => (),
+            Instruction::Quit => break,
+            Instruction::SetApp(new_app) => {
+                let menu = stack.last_mut().expect("no menu open");
+                menu.app = new_app;
+                menu.source = None;
+                status = None;
+            }
+            Instruction::OpenMenu(name) => match Menu::load(name) {
+                Ok(menu) => {
+                    stack.push(menu);
+                    status = None;
+                }
+                Err(err) => status = error_status(err),
+            },
+            Instruction::OpenConfig(config) => match Menu::generated(*config) {
+                Ok(menu) => {
+                    stack.push(menu);
+                    status = None;
+                }
+                Err(err) => status = error_status(err),
             },
+            Instruction::Push(app) => {
+                let keys = stack.last().expect("no menu open").keys.clone();
+                stack.push(Menu {
+                    app,
+                    keys,
+                    source: None,
+                });
+            }
+            Instruction::Back => {
+                stack.pop();
+                if stack.is_empty() {
+                    break;
+                }
+                status = None;
+            }
+            Instruction::HoldOutput(mut process) => {
+                terminal.clear()?;
+                terminal.move_cursor(1, 1)?;
+                terminal.flush()?;
+                drop(terminal);
 
-            Event::Resize(w, h) => app.handle_resize(w, h),
+                let child = process.spawn().expect("failed to spawn");
+                cmd = Some((child, process.timeout));
+                break;
+            }
+            Instruction::Print(lines) => {
+                terminal.clear()?;
+                terminal.flush()?;
+                drop(terminal);
+
+                for line in lines {
+                    println!("{line}");
+                }
+                process::exit(0);
+            }
         }
 
-        app.draw(&mut terminal)?;
+        stack
+            .last()
+            .expect("no menu open")
+            .app
+            .draw(&mut terminal)?;
+        if let Some(status) = &status {
+            draw_status(&mut terminal, status)?;
+        }
     }
 
-    if let Some(mut child) = cmd {
-        let res = child.wait();
+    if let Some((mut child, timeout)) = cmd {
+        let res = app::action::wait(&mut child, timeout);
         let mut temp = stdout().into_raw_mode()?;
 
         temp.print("\n")?;
-        let code = res.expect("failed to get exit code.");
-        if !code.success() {
-            temp.print(format!("Process exited with code {code}.\r\n"))?;
+        match res {
+            Ok(code) if !code.success() => {
+                temp.print(format!("Process exited with code {code}.\r\n"))?;
+            }
+            Ok(_) => (),
+            Err(err) if err.kind() == io::ErrorKind::TimedOut => {
+                temp.print(format!("Process {err}.\r\n"))?;
+            }
+            Err(err) => panic!("failed to get exit code: {err}"),
         }
 
         temp.print("Press any key to exit.")?;
@@ -0,0 +1,20 @@
+/// Finds `needle` in `haystack`, returning the char positions it covers.
+pub fn find(haystack: &str, needle: &str) -> Option<Vec<usize>> {
+    let start = haystack.find(needle)?;
+    let start = haystack[..start].chars().count();
+    Some((start..start + needle.chars().count()).collect())
+}
+
+/// Finds the keyword in a space-separated list that contains `needle`.
+pub fn find_keyword<'a>(keywords: &'a str, needle: &str) -> Option<&'a str> {
+    if !keywords.contains(needle) {
+        return None;
+    }
+
+    Some(
+        keywords
+            .split_whitespace()
+            .find(|keyword| keyword.contains(needle))
+            .unwrap_or(keywords),
+    )
+}
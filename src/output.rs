@@ -5,6 +5,20 @@ use std::{
 
 use termion::raw::RawTerminal;
 
+use crate::style::Style;
+
+/// Shortens `text` to at most `width` chars, ending with an ellipsis if it
+/// was cut off.
+pub fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+
+    let mut text: String = text.chars().take(width.saturating_sub(1)).collect();
+    text.push('…');
+    text
+}
+
 pub trait Output: Write {
     fn clear(&mut self) -> io::Result<()> {
         write!(
@@ -22,6 +36,29 @@ pub trait Output: Write {
     fn move_cursor(&mut self, col: usize, ln: usize) -> io::Result<()> {
         write!(self, "{}", termion::cursor::Goto(col as u16, ln as u16))
     }
+
+    /// Prints `text` in the current style, switching to `highlight` for the
+    /// chars at `positions` and back to `base` after each.
+    fn highlighted(
+        &mut self,
+        text: &str,
+        positions: &[usize],
+        base: &Style,
+        highlight: &Style,
+    ) -> io::Result<()> {
+        for (i, ch) in text.chars().enumerate() {
+            if positions.contains(&i) {
+                write!(self, "{highlight}{ch}\x1b[0m{base}")?;
+            } else {
+                write!(self, "{ch}")?;
+            }
+        }
+        Ok(())
+    }
+
+    fn divider(&mut self, width: usize, style: &Style) -> io::Result<()> {
+        write!(self, "{style}{}\x1b[0m", "─".repeat(width))
+    }
 }
 
 impl Output for RawTerminal<Stdout> {}
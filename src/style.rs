@@ -1,10 +1,19 @@
-use std::{fmt::Display, ops::Deref};
+use std::{env, fmt::Display, ops::Deref, str::FromStr, sync::OnceLock};
 
-use serde::Deserialize;
+use serde::{
+    de::{self, value::MapAccessDeserializer, MapAccess, Visitor},
+    Deserialize, Deserializer,
+};
+
+use crate::theme;
+
+const NAMES: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
 
 #[derive(Default, Clone, Copy, PartialEq, Eq, Deserialize)]
 #[allow(unused)]
-#[serde(rename_all = "lowercase")]
+#[serde(try_from = "String")]
 pub enum Color {
     #[default]
     Default,
@@ -16,6 +25,11 @@ pub enum Color {
     Magenta,
     Cyan,
     White,
+    /// One of the bright variants of the basic colors, by index (0-7).
+    Bright(u8),
+    /// An index into the 256-color palette.
+    Ansi(u8),
+    Rgb(u8, u8, u8),
 }
 
 impl Color {
@@ -30,12 +44,119 @@ impl Color {
             Color::Magenta => 5,
             Color::Cyan => 6,
             Color::White => 7,
+            Color::Bright(code) => code,
+            Color::Ansi(code) => code,
+            Color::Rgb(r, g, b) => nearest_ansi(r, g, b),
+        }
+    }
+
+    /// SGR parameters for this color, where `base` is 30 for the foreground
+    /// and 40 for the background.
+    pub fn sgr(self, base: u8) -> String {
+        match self {
+            Color::Bright(code) => (base + 60 + code).to_string(),
+            Color::Ansi(code) => format!("{};5;{code}", base + 8),
+            Color::Rgb(r, g, b) if truecolor() => format!("{};2;{r};{g};{b}", base + 8),
+            Color::Rgb(..) => format!("{};5;{}", base + 8, self.code()),
+            _ => (base + self.code()).to_string(),
         }
     }
 }
 
-#[derive(Deserialize, Default)]
-#[serde(default)]
+impl FromStr for Color {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "default" {
+            return Ok(Color::Default);
+        }
+        if let Some(hex) = s.strip_prefix('#') {
+            if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(format!("invalid hex color \"{s}\""));
+            }
+            let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).expect("hex digits");
+            return Ok(Color::Rgb(channel(0), channel(2), channel(4)));
+        }
+        if let Some(code) = s.strip_prefix("ansi:") {
+            return code
+                .parse()
+                .map(Color::Ansi)
+                .map_err(|_| format!("invalid ansi color \"{s}\""));
+        }
+
+        let (bright, name) = match s.strip_prefix("bright") {
+            Some(name) => (true, name.strip_prefix('-').unwrap_or(name)),
+            None => (false, s),
+        };
+        let Some(code) = NAMES.iter().position(|n| *n == name) else {
+            return Err(format!("unknown color \"{s}\""));
+        };
+
+        Ok(match (bright, code) {
+            (true, code) => Color::Bright(code as u8),
+            (false, 0) => Color::Black,
+            (false, 1) => Color::Red,
+            (false, 2) => Color::Green,
+            (false, 3) => Color::Yellow,
+            (false, 4) => Color::Blue,
+            (false, 5) => Color::Magenta,
+            (false, 6) => Color::Cyan,
+            (false, _) => Color::White,
+        })
+    }
+}
+
+impl TryFrom<String> for Color {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+fn truecolor() -> bool {
+    static TRUECOLOR: OnceLock<bool> = OnceLock::new();
+    *TRUECOLOR.get_or_init(|| {
+        env::var("COLORTERM").is_ok_and(|term| term == "truecolor" || term == "24bit")
+    })
+}
+
+/// Finds the closest color in the 6x6x6 cube or grayscale ramp of the
+/// 256-color palette.
+fn nearest_ansi(r: u8, g: u8, b: u8) -> u8 {
+    const STEPS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+    let step = |v: u8| match v {
+        0..48 => 0,
+        48..115 => 1,
+        _ => (v - 35) / 40,
+    };
+    let distance = |(r2, g2, b2): (u8, u8, u8)| {
+        [(r, r2), (g, g2), (b, b2)]
+            .iter()
+            .map(|(a, b)| (*a as i32 - *b as i32).pow(2))
+            .sum::<i32>()
+    };
+
+    let (ri, gi, bi) = (step(r), step(g), step(b));
+    let cube = (STEPS[ri as usize], STEPS[gi as usize], STEPS[bi as usize]);
+
+    let average = (r as u32 + g as u32 + b as u32) / 3;
+    let gray_index = if average > 238 {
+        23
+    } else {
+        (average.saturating_sub(3) / 10) as u8
+    };
+    let gray = 8 + gray_index * 10;
+
+    if distance((gray, gray, gray)) < distance(cube) {
+        232 + gray_index
+    } else {
+        16 + 36 * ri + 6 * gi + bi
+    }
+}
+
+#[derive(Default, Clone)]
 pub struct Style {
     pub fg: Color,
     pub bg: Color,
@@ -46,36 +167,94 @@ pub struct Style {
     pub underline: bool,
 }
 
+/// A style as written in a config, optionally based on a named theme style.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct StyleDef {
+    extends: Option<String>,
+
+    fg: Option<Color>,
+    bg: Option<Color>,
+
+    dim: Option<bool>,
+    bold: Option<bool>,
+    italic: Option<bool>,
+    underline: Option<bool>,
+}
+
+impl TryFrom<StyleDef> for Style {
+    type Error = String;
+
+    fn try_from(def: StyleDef) -> Result<Self, Self::Error> {
+        let base = match def.extends {
+            Some(name) => theme::lookup(&name)?,
+            None => Style::default(),
+        };
+
+        Ok(Style {
+            fg: def.fg.unwrap_or(base.fg),
+            bg: def.bg.unwrap_or(base.bg),
+            dim: def.dim.unwrap_or(base.dim),
+            bold: def.bold.unwrap_or(base.bold),
+            italic: def.italic.unwrap_or(base.italic),
+            underline: def.underline.unwrap_or(base.underline),
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for Style {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct StyleVisitor;
+
+        impl<'de> Visitor<'de> for StyleVisitor {
+            type Value = Style;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "a style or the name of a theme style")
+            }
+
+            fn visit_str<E: de::Error>(self, name: &str) -> Result<Style, E> {
+                theme::lookup(name).map_err(E::custom)
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Style, A::Error> {
+                let def = StyleDef::deserialize(MapAccessDeserializer::new(map))?;
+                Style::try_from(def).map_err(de::Error::custom)
+            }
+        }
+
+        deserializer.deserialize_any(StyleVisitor)
+    }
+}
+
 impl Display for Style {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut code = Vec::new();
 
         if self.fg != Color::Default {
-            code.push(self.fg.code() + 30);
+            code.push(self.fg.sgr(30));
         }
         if self.bg != Color::Default {
-            code.push(self.bg.code() + 40);
+            code.push(self.bg.sgr(40));
         }
         if self.bold {
-            code.push(1);
+            code.push(String::from("1"));
         }
         if self.dim {
-            code.push(2);
+            code.push(String::from("2"));
         }
         if self.italic {
-            code.push(3);
+            code.push(String::from("3"));
         }
         if self.underline {
-            code.push(4);
+            code.push(String::from("4"));
         }
 
-        let code: Vec<_> = code.iter().map(ToString::to_string).collect();
-
         write!(f, "\x1b[{code}m", code = code.join(";"))
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone)]
 pub struct Styled {
     pub text: String,
 
@@ -110,3 +289,61 @@ impl Deref for Styled {
         &self.text
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> Result<Color, String> {
+        s.parse()
+    }
+
+    #[test]
+    fn hex_colors_need_six_digits() {
+        assert!(parse("#1a2B3c") == Ok(Color::Rgb(0x1a, 0x2b, 0x3c)));
+        for s in ["#fff", "#1a2b3c4", "#", "#+1+2+3", "#gggggg", "#ééé"] {
+            assert!(parse(s).is_err(), "{s}");
+        }
+    }
+
+    #[test]
+    fn bright_colors_take_an_optional_dash() {
+        assert!(parse("brightred") == Ok(Color::Bright(1)));
+        assert!(parse("bright-white") == Ok(Color::Bright(7)));
+        assert!(parse("red") == Ok(Color::Red));
+        for s in ["bright", "bright-", "bright--red", "brightdefault", "Red"] {
+            assert!(parse(s).is_err(), "{s}");
+        }
+    }
+
+    #[test]
+    fn ansi_colors_are_palette_indices() {
+        assert!(parse("ansi:0") == Ok(Color::Ansi(0)));
+        assert!(parse("ansi:255") == Ok(Color::Ansi(255)));
+        assert!(parse("ansi:256").is_err());
+        assert!(parse("ansi:").is_err());
+    }
+
+    #[test]
+    fn rgb_maps_to_the_nearest_cube_color() {
+        assert_eq!(nearest_ansi(0, 0, 0), 16);
+        assert_eq!(nearest_ansi(255, 255, 255), 231);
+        assert_eq!(nearest_ansi(255, 0, 0), 196);
+        assert_eq!(nearest_ansi(95, 135, 175), 67);
+        // Channels round to the nearest of 0, 95, 135, 175, 215 and 255.
+        assert_eq!(nearest_ansi(255, 47, 0), 196);
+        assert_eq!(nearest_ansi(255, 48, 0), 202);
+        assert_eq!(nearest_ansi(255, 114, 0), 202);
+        assert_eq!(nearest_ansi(255, 115, 0), 208);
+        assert_eq!(nearest_ansi(255, 235, 0), 226);
+    }
+
+    #[test]
+    fn grays_map_to_the_grayscale_ramp() {
+        assert_eq!(nearest_ansi(8, 8, 8), 232);
+        assert_eq!(nearest_ansi(128, 128, 128), 244);
+        assert_eq!(nearest_ansi(238, 238, 238), 255);
+        // Closer to the cube's white than the lightest gray.
+        assert_eq!(nearest_ansi(250, 250, 250), 231);
+    }
+}
@@ -0,0 +1,121 @@
+use std::{cell::RefCell, fs, io, path::PathBuf};
+
+use serde::Deserialize;
+
+use crate::{
+    config::{Config, Error},
+    style::{Color, Style},
+};
+
+thread_local! {
+    static CURRENT: RefCell<Option<Theme>> = const { RefCell::new(None) };
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Theme {
+    pub prompt: Style,
+    pub placeholder: Style,
+    pub icon: Style,
+    pub entry: Style,
+    pub selected_entry: Style,
+    pub header: Style,
+    pub divider: Style,
+    #[serde(rename = "match")]
+    pub highlight: Style,
+    pub error: Style,
+    pub status_bar: Style,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            prompt: Style::default(),
+            placeholder: Style {
+                dim: true,
+                ..Default::default()
+            },
+            icon: Style::default(),
+            entry: Style::default(),
+            selected_entry: Style::default(),
+            header: Style {
+                bold: true,
+                ..Default::default()
+            },
+            divider: Style {
+                fg: Color::Ansi(235),
+                ..Default::default()
+            },
+            highlight: Style {
+                bold: true,
+                ..Default::default()
+            },
+            error: Style {
+                fg: Color::Red,
+                ..Default::default()
+            },
+            status_bar: Style {
+                fg: Color::Red,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl Theme {
+    /// Loads a named theme from `themes/<name>.json`, or the global
+    /// `theme.json` if no name is given. A missing global theme falls back to
+    /// the defaults.
+    pub fn load(name: Option<&str>) -> Result<Self, Error> {
+        let path = Self::path(name);
+        match fs::read_to_string(&path) {
+            Ok(file) => serde_json::from_str(&file).map_err(Error::Parse),
+            Err(err) if name.is_none() && err.kind() == io::ErrorKind::NotFound => {
+                Ok(Self::default())
+            }
+            Err(err) => Err(Error::Io(err)),
+        }
+    }
+
+    pub fn path(name: Option<&str>) -> PathBuf {
+        match name {
+            Some(name) => Config::dir().join("themes").join(format!("{name}.json")),
+            None => Config::dir().join("theme.json"),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Style> {
+        Some(match name {
+            "prompt" => &self.prompt,
+            "placeholder" => &self.placeholder,
+            "icon" => &self.icon,
+            "entry" => &self.entry,
+            "selectedEntry" => &self.selected_entry,
+            "header" => &self.header,
+            "divider" => &self.divider,
+            "match" => &self.highlight,
+            "error" => &self.error,
+            "statusBar" => &self.status_bar,
+            _ => return None,
+        })
+    }
+
+    /// Makes this theme available to `Style` references while `f` runs.
+    pub fn scope<T>(&self, f: impl FnOnce() -> T) -> T {
+        CURRENT.with_borrow_mut(|current| *current = Some(self.clone()));
+        let result = f();
+        CURRENT.with_borrow_mut(|current| *current = None);
+        result
+    }
+}
+
+/// Looks up a named style in the theme of the config being parsed.
+pub fn lookup(name: &str) -> Result<Style, String> {
+    CURRENT.with_borrow(|current| match current {
+        Some(theme) => theme
+            .get(name)
+            .cloned()
+            .ok_or_else(|| format!("unknown theme style \"{name}\"")),
+        None => Err(format!("cannot reference theme style \"{name}\" here")),
+    })
+}